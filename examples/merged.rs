@@ -1,5 +1,10 @@
-use flatbuffers::FlatBufferBuilder;
-use lean_buffer::traits::{AdapterExt, Factory, FactoryExt};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+
+use lean_buffer::traits::{AdapterExt, Factory, FactoryExt, LeanBuffer};
 
 
 struct EntityMixed {
@@ -92,13 +97,288 @@ struct Entity {
   t_float: f32
 }
 
+struct Address {
+  street: String,
+  number: u16,
+}
+
+struct Customer {
+  name: String,
+  address: Address,
+}
+
+struct OrderLine {
+  sku: String,
+  quantity: u32,
+}
+
+struct Order {
+  id: u64,
+  customer: Customer,
+  delivery: Address,
+  lines: Vec<OrderLine>,
+}
+
+#[allow(dead_code)]
+enum Status {
+  Pending,
+  Shipped = 5,
+  Delivered,
+  Unknown = 300,
+}
+
+struct Parcel {
+  weight: u32,
+  status: Status,
+  history: Vec<Status>,
+}
+
+struct Ping {
+  sequence: u32,
+}
+
+#[allow(dead_code)]
+enum Message {
+  Unknown,
+  Ping(Ping),
+  Data { channel: u16, payload: Vec<u8> },
+  Text(String, bool),
+}
+
+struct Meters(f64);
+
+struct Pair(u32, String, Meters);
+
+struct Header {
+  topic: String,
+  sequence: u32,
+}
+
+struct Reading {
+  celsius: f32,
+}
+
+struct Envelope<T: LeanBuffer> {
+  header: Header,
+  body: T,
+}
+
+struct Series<T: LeanBuffer> {
+  name: String,
+  samples: Vec<T>,
+}
+
+struct Samples<T: LeanBuffer>(Vec<T>);
+
+struct Telemetry {
+  id: [u8; 16],
+  position: [f32; 3],
+  samples: [i64; 4],
+  flags: [bool; 2],
+}
+
+struct Avatar {
+  url: String,
+}
+
+struct Profile {
+  nickname: Option<String>,
+  scores: Option<Vec<u32>>,
+  tags: Option<Vec<String>>,
+  avatar: Option<Avatar>,
+  checksum: Option<[u8; 4]>,
+}
+
+struct Sensor {
+  name: String,
+  metadata: HashMap<String, f64>,
+  labels: BTreeMap<u32, String>,
+}
+
+struct Record {
+  id: u128,
+  delta: i128,
+  len: usize,
+  offset: isize,
+  capacity: Option<usize>,
+}
+
+struct Model {
+  name: Arc<str>,
+  description: Cow<'static, str>,
+  weights: Box<[u8]>,
+  revision: Rc<u32>,
+  parent: Option<Box<Model>>,
+}
+
+type AccountId = u64;
+type Tags = Vec<String>;
+
+struct Account {
+  id: AccountId,
+  name: std::string::String,
+  scores: std::vec::Vec<core::primitive::u32>,
+  tags: Option<Tags>,
+  friends: Vec<AccountId>,
+  ranks: std::collections::HashMap<std::string::String, u32>,
+  avatar: [core::primitive::u8; 4],
+}
+
+#[allow(dead_code)]
+struct Session {
+  user: String,
+  cache: Vec<String>,
+  opened: Instant,
+  marker: std::marker::PhantomData<u8>,
+  visits: u32,
+}
+
+struct DeviceV1 {
+  name: String,
+  firmware: String,
+  location: String,
+}
+
+struct Device {
+  serial: u32,
+  room: String,
+  name: String,
+}
+
+struct Settings {
+  retries: u32,
+  ratio: f32,
+  verbose: bool,
+}
+
+struct Window {
+  width: u32,
+  height: u32,
+  title: String,
+}
+
+struct WindowV1 {
+  width: u32,
+  height: u32,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window {
+            width: 800,
+            height: 600,
+            title: "untitled".to_string(),
+        }
+    }
+}
+
+struct Login {
+  attempts: u32,
+  user: String,
+  labels: BTreeMap<u32, u32>,
+}
+
+struct Anonymous {
+  attempts: u32,
+}
+
+#[allow(dead_code)]
+enum Level {
+  Low,
+  High,
+}
+
+#[allow(dead_code)]
+struct Note {
+  text: String,
+}
+
+#[allow(dead_code)]
+enum Payload {
+  Empty,
+  Note(Note),
+  Levels { levels: Vec<Level> },
+}
+
+#[allow(dead_code)]
+struct Packet {
+  topic: String,
+  payload: Payload,
+  counters: BTreeMap<String, u64>,
+  digest: [u8; 4],
+  id: u128,
+  notes: Vec<Note>,
+}
+
+struct Greeting {
+  text: String,
+}
+
+struct Heartbeat {
+  uptime: u64,
+}
+
+struct Tick {
+  sensor: u16,
+  value: f32,
+}
+
+struct Event {
+  kind: u8,
+  detail: String,
+}
+
+struct Invoice {
+  number: u32,
+  total: f64,
+}
+
+struct Receipt {
+  number: u32,
+  total: f64,
+}
+
+struct Label {
+  text: String,
+}
+
+struct Builderish {
+  builder: u32,
+  table: u16,
+  default_object: String,
+  wip_offset_unfinished: Vec<u8>,
+  a: [u8; 2],
+  n: Label,
+  inner: Option<String>,
+  key: BTreeMap<u32, u32>,
+  r#loop: BTreeMap<u32, u32>,
+  r#ref: Vec<Label>,
+}
+
+impl Default for Builderish {
+    fn default() -> Self {
+        Builderish {
+            builder: 7,
+            table: 0,
+            default_object: "default".to_string(),
+            wip_offset_unfinished: vec![],
+            a: [0; 2],
+            n: Label {
+                text: "n".to_string(),
+            },
+            inner: None,
+            key: BTreeMap::new(),
+            r#loop: BTreeMap::new(),
+            r#ref: vec![],
+        }
+    }
+}
+
 // See `build.rs`, might require multiple `cargo build` invocations
 // also, to generate `*_lb_gen.rs`, each program in examples must be run at least once
 include!(concat!(env!("OUT_DIR"), "/merged_gen.lb.rs"));
 
 fn main() {
-    let mut builder = FlatBufferBuilder::new();
-
     let factory = Factory::<Entity> {
         phantom_data: std::marker::PhantomData,
     };
@@ -111,20 +391,14 @@ fn main() {
     let a1 = Box::new(e1) as Box<dyn AdapterExt>;
 
     // flatten
-    a1.flatten(&mut builder);
-    let data = builder.finished_data();
+    let data = a1.to_bytes();
 
     // inflate
-    let first_offset: usize = data[0].into();
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
 
-    unsafe {
-        let mut table = flatbuffers::Table::new(data, first_offset);
-        let resurrected_e1 = f.inflate(&mut table);
-
-        if resurrected_e1.t_i64 == e1_t_i64 {
-            println!("Hello world! {}", resurrected_e1.t_i64);
-        } else {
-            println!("Goodbye cruel world! {}", resurrected_e1.t_i64);
-        }
+    if resurrected_e1.t_i64 == e1_t_i64 {
+        println!("Hello world! {}", resurrected_e1.t_i64);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.t_i64);
     }
 }
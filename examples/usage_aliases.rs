@@ -0,0 +1,65 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+type AccountId = u64;
+type Tags = Vec<String>;
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+// A derive cannot look up type aliases, so they are declared again
+#[derive(LeanBufferWrite)]
+#[lean_buffer(aliases(AccountId = "u64", Tags = "Vec<String>"))]
+struct Account {
+    id: AccountId,
+    name: std::string::String,
+    scores: std::vec::Vec<core::primitive::u32>,
+    tags: Option<Tags>,
+    friends: Vec<AccountId>,
+    ranks: std::collections::HashMap<std::string::String, u32>,
+    avatar: [core::primitive::u8; 4],
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Account_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Account> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Account>>;
+    let mut e1 = f.new_object();
+
+    e1.id = 0x1337833F;
+    e1.name = "Grace".to_string();
+    e1.scores = vec![3, 1, 4];
+    e1.tags = Some(vec!["admiral".to_string()]);
+    e1.friends = vec![1, 2, 3];
+    e1.ranks.insert("chess".to_string(), 1);
+    e1.avatar = [1, 2, 3, 4];
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.id == 0x1337833F
+        && resurrected_e1.scores == [3, 1, 4]
+        && resurrected_e1.tags == Some(vec!["admiral".to_string()])
+        && resurrected_e1.friends == [1, 2, 3]
+        && resurrected_e1.ranks.get("chess") == Some(&1)
+        && resurrected_e1.avatar == [1, 2, 3, 4]
+    {
+        println!("Hello world! {}", resurrected_e1.name);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.name);
+    }
+}
@@ -0,0 +1,53 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Telemetry {
+    id: [u8; 16],
+    position: [f32; 3],
+    samples: [i64; 4],
+    flags: [bool; 2],
+}
+
+mod telemetry_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Telemetry_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Telemetry> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Telemetry>>;
+    let mut e1 = f.new_object();
+
+    e1.id = *b"0123456789abcdef";
+    e1.position = [1.0, -2.5, 3.25];
+    e1.samples = [i64::MIN, -1, 0, i64::MAX];
+    e1.flags = [true, false];
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if &resurrected_e1.id == b"0123456789abcdef"
+        && resurrected_e1.position == [1.0, -2.5, 3.25]
+        && resurrected_e1.samples == [i64::MIN, -1, 0, i64::MAX]
+        && resurrected_e1.flags == [true, false]
+    {
+        println!("Hello world! {:?}", resurrected_e1.position);
+    } else {
+        println!("Goodbye cruel world! {:?}", resurrected_e1.position);
+    }
+}
@@ -0,0 +1,56 @@
+use std::rc::Rc;
+
+use flatbuffers::FlatBufferBuilder;
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{BatchExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Tick {
+    sensor: u16,
+    value: f32,
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Tick_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Tick> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Tick>>;
+
+    let ticks: Vec<Tick> = (0..1000)
+        .map(|i| Tick {
+            sensor: i % 16,
+            value: i as f32 * 0.5,
+        })
+        .collect();
+
+    // flatten, every tick into one buffer
+    let mut builder = FlatBufferBuilder::new();
+    ticks.flatten_batch(&mut builder);
+    let data = builder.finished_data();
+
+    // inflate all of them at once
+    let inflated = f.from_batch_bytes(data).unwrap();
+
+    // or one at a time
+    let last = f
+        .batch(data, &Default::default())
+        .unwrap()
+        .map(Result::unwrap)
+        .last()
+        .unwrap();
+
+    if inflated.len() == 1000 && inflated[3].value == 1.5 && last.sensor == 7 {
+        println!("Hello world! {}", last.value);
+    } else {
+        println!("Goodbye cruel world! {}", last.value);
+    }
+}
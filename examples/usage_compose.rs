@@ -0,0 +1,74 @@
+use std::rc::Rc;
+
+use flatbuffers::{read_scalar, FlatBufferBuilder, ForwardsUOffset, Table};
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Greeting {
+    text: String,
+}
+
+#[derive(LeanBufferWrite)]
+struct Heartbeat {
+    uptime: u64,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod greeting_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Greeting_lb_gen.rs"));
+}
+
+mod heartbeat_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Heartbeat_lb_gen.rs"));
+}
+
+fn main() {
+    let greeting = Greeting {
+        text: "hello".to_string(),
+    };
+    let heartbeat = Heartbeat { uptime: 0x1337833F };
+
+    // flatten both into one builder, then wrap them in a table of our own
+    let mut builder = FlatBufferBuilder::new();
+    let greeting_offset = greeting.flatten_table(&mut builder);
+    let heartbeat_offset = heartbeat.flatten_table(&mut builder);
+    let wip_offset_unfinished = builder.start_table();
+    builder.push_slot_always(4, greeting_offset);
+    builder.push_slot_always(6, heartbeat_offset);
+    let message = builder.end_table(wip_offset_unfinished);
+    builder.finish_minimal(message);
+    let data = builder.finished_data();
+
+    // inflate each of them from its slot
+    let greetings = Rc::new(Factory::<Greeting> {
+        phantom_data: std::marker::PhantomData,
+    }) as Rc<dyn FactoryExt<Greeting>>;
+    let heartbeats = Rc::new(Factory::<Heartbeat> {
+        phantom_data: std::marker::PhantomData,
+    }) as Rc<dyn FactoryExt<Heartbeat>>;
+
+    let (greeting, heartbeat) = unsafe {
+        let message = Table::new(data, read_scalar::<u32>(data) as usize);
+        let mut greeting_table = message.get::<ForwardsUOffset<Table>>(4, None).unwrap();
+        let mut heartbeat_table = message.get::<ForwardsUOffset<Table>>(6, None).unwrap();
+        (
+            greetings.try_inflate(&mut greeting_table).unwrap(),
+            heartbeats.try_inflate(&mut heartbeat_table).unwrap(),
+        )
+    };
+
+    if greeting.text == "hello" && heartbeat.uptime == 0x1337833F {
+        println!("Hello world! {}", greeting.text);
+    } else {
+        println!("Goodbye cruel world! {}", greeting.text);
+    }
+}
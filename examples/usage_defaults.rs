@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+// A scalar that equals its default is left out, and restored from it
+#[derive(LeanBufferWrite)]
+struct Settings {
+    #[lean_buffer(default = 3)]
+    retries: u32,
+    #[lean_buffer(default = 0.5)]
+    ratio: f32,
+    #[lean_buffer(default = true)]
+    verbose: bool,
+}
+
+// Or start from the `Default` impl of the struct
+#[derive(LeanBufferWrite)]
+#[lean_buffer(default)]
+struct Window {
+    width: u32,
+    height: u32,
+    title: String,
+}
+
+// An earlier layout, without the title
+#[derive(LeanBufferWrite)]
+struct WindowV1 {
+    width: u32,
+    height: u32,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window {
+            width: 800,
+            height: 600,
+            title: "untitled".to_string(),
+        }
+    }
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod settings_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Settings_lb_gen.rs"));
+}
+
+mod window_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Window_lb_gen.rs"));
+}
+
+mod window_v1_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/WindowV1_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Settings> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Settings>>;
+    let mut e1 = f.new_object();
+
+    e1.ratio = 0.75;
+    let e1_retries = e1.retries;
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    let factory = Factory::<Window> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let g = Rc::new(factory) as Rc<dyn FactoryExt<Window>>;
+    let mut e2 = g.new_object();
+
+    e2.height = 1080;
+
+    let a2 = Box::new(e2) as Box<dyn AdapterExt>;
+    let window_data = a2.to_bytes();
+
+    let a3 = Box::new(WindowV1 {
+        width: 640,
+        height: 480,
+    }) as Box<dyn AdapterExt>;
+    let window_v1_data = a3.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+    let resurrected_e2 = g.from_bytes(&window_data).unwrap();
+    // the absent title is taken from the `Default` impl
+    let upgraded = g.from_bytes(&window_v1_data).unwrap();
+
+    if e1_retries == 3
+        && resurrected_e1.retries == 3
+        && resurrected_e1.ratio == 0.75
+        && resurrected_e1.verbose
+        && resurrected_e2.width == 800
+        && resurrected_e2.height == 1080
+        && resurrected_e2.title == "untitled"
+        && upgraded.width == 640
+        && upgraded.title == "untitled"
+    {
+        println!("Hello world! {}", resurrected_e2.title);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e2.title);
+    }
+}
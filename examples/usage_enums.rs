@@ -0,0 +1,65 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite, PartialEq, Debug)]
+enum Status {
+    Pending,
+    Shipped = 5,
+    Delivered,
+    #[lean_buffer(fallback)]
+    Unknown = 300,
+}
+
+#[derive(LeanBufferWrite)]
+struct Parcel {
+    weight: u32,
+    status: Status,
+    history: Vec<Status>,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod status_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Status_lb_gen.rs"));
+}
+
+mod parcel_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Parcel_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Parcel> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Parcel>>;
+    let mut e1 = f.new_object();
+
+    e1.weight = 1200;
+    e1.status = Status::Delivered;
+    e1.history = vec![Status::Pending, Status::Shipped, Status::Delivered];
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.status == Status::Delivered
+        && resurrected_e1.history == vec![Status::Pending, Status::Shipped, Status::Delivered]
+    {
+        println!("Hello world! {:?}", resurrected_e1.status);
+    } else {
+        println!("Goodbye cruel world! {:?}", resurrected_e1.status);
+    }
+}
@@ -0,0 +1,63 @@
+use std::{io::Cursor, rc::Rc};
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Error, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Event {
+    kind: u8,
+    detail: String,
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Event_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Event> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Event>>;
+
+    // write frames one after another, e.g. into a pipe or a file
+    let mut stream = Vec::new();
+    for (kind, detail) in [(1, "connected"), (2, "ready"), (3, "closed")] {
+        let event = Event {
+            kind,
+            detail: detail.to_string(),
+        };
+        event.write_frame(&mut stream).unwrap();
+    }
+
+    // read them back until the end of the stream
+    let mut reader = Cursor::new(&stream);
+    let mut events = Vec::new();
+    while let Some(event) = f.read_frame(&mut reader, 1024).unwrap() {
+        events.push(event);
+    }
+
+    // a frame beyond the limit is rejected before it is read
+    let too_large = f.read_frame(&mut Cursor::new(&stream), 8).err().unwrap();
+    let too_large = too_large.get_ref().unwrap().downcast_ref::<Error>();
+
+    // a single frame
+    let data = events[1].to_size_prefixed_bytes();
+    let ready = f.from_size_prefixed_bytes(&data).unwrap();
+    let truncated = f.from_size_prefixed_bytes(&data[..data.len() - 1]);
+
+    if events.len() == 3
+        && events[2].detail == "closed"
+        && matches!(too_large, Some(Error::FrameTooLarge { max: 8, .. }))
+        && ready.kind == 2
+        && matches!(truncated, Err(Error::Invalid(_)))
+    {
+        println!("Hello world! {}", events[0].detail);
+    } else {
+        println!("Goodbye cruel world! {}", events[0].detail);
+    }
+}
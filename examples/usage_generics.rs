@@ -0,0 +1,118 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt, LeanBuffer},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Header {
+    topic: String,
+    sequence: u32,
+}
+
+#[derive(LeanBufferWrite)]
+struct Reading {
+    celsius: f32,
+}
+
+// The bounds are copied onto the generated impls
+#[derive(LeanBufferWrite)]
+struct Envelope<T: LeanBuffer> {
+    header: Header,
+    body: T,
+}
+
+// A type parameter may also be the element of a vector
+#[derive(LeanBufferWrite)]
+struct Series<T: LeanBuffer> {
+    name: String,
+    samples: Vec<T>,
+}
+
+#[derive(LeanBufferWrite)]
+struct Samples<T: LeanBuffer>(Vec<T>);
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod header_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Header_lb_gen.rs"));
+}
+
+mod reading_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Reading_lb_gen.rs"));
+}
+
+mod envelope_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Envelope_lb_gen.rs"));
+}
+
+mod series_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Series_lb_gen.rs"));
+}
+
+mod samples_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Samples_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Envelope<Reading>> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Envelope<Reading>>>;
+    let mut e1 = f.new_object();
+
+    e1.header.topic = "greenhouse".to_string();
+    e1.header.sequence = 7;
+    e1.body.celsius = 21.5;
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    let series = Series::<Reading> {
+        name: "greenhouse".to_string(),
+        samples: vec![Reading { celsius: 19.0 }, Reading { celsius: 23.5 }],
+    };
+    let series_data = series.to_bytes();
+
+    let samples = Samples::<Header>(vec![Header {
+        topic: "door".to_string(),
+        sequence: 1,
+    }]);
+    let samples_data = samples.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+    let resurrected_series = Factory::<Series<Reading>> {
+        phantom_data: std::marker::PhantomData,
+    }
+    .from_bytes(&series_data)
+    .unwrap();
+    let resurrected_samples = Factory::<Samples<Header>> {
+        phantom_data: std::marker::PhantomData,
+    }
+    .from_bytes(&samples_data)
+    .unwrap();
+
+    if resurrected_e1.header.topic == "greenhouse"
+        && resurrected_e1.header.sequence == 7
+        && resurrected_e1.body.celsius == 21.5
+        && resurrected_series.samples.len() == 2
+        && resurrected_series.samples[1].celsius == 23.5
+        && resurrected_samples.0[0].topic == "door"
+    {
+        println!("Hello world! {}", resurrected_e1.header.topic);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.header.topic);
+    }
+}
@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, BatchExt, Error, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+#[lean_buffer(file_identifier = "INVC")]
+struct Invoice {
+    number: u32,
+    total: f64,
+}
+
+// derived from the name of the type
+#[derive(LeanBufferWrite)]
+#[lean_buffer(file_identifier)]
+struct Receipt {
+    number: u32,
+    total: f64,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod invoice_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Invoice_lb_gen.rs"));
+}
+
+mod receipt_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Receipt_lb_gen.rs"));
+}
+
+fn main() {
+    let invoices = Rc::new(Factory::<Invoice> {
+        phantom_data: std::marker::PhantomData,
+    }) as Rc<dyn FactoryExt<Invoice>>;
+    let receipts = Rc::new(Factory::<Receipt> {
+        phantom_data: std::marker::PhantomData,
+    }) as Rc<dyn FactoryExt<Receipt>>;
+
+    let invoice = Invoice {
+        number: 42,
+        total: 99.5,
+    };
+
+    // flatten
+    let data = invoice.to_bytes();
+
+    // inflate, the same layout is rejected as another type
+    let inflated = invoices.from_bytes(&data).unwrap();
+    let mismatched = receipts.from_bytes(&data);
+
+    // batches and size-prefixed buffers carry it as well
+    let batch = [inflated].batch_to_bytes();
+    let mismatched_batch = receipts.from_batch_bytes(&batch);
+    let prefixed = Receipt {
+        number: 7,
+        total: 1.25,
+    }
+    .to_size_prefixed_bytes();
+    let receipt = receipts.from_size_prefixed_bytes(&prefixed).unwrap();
+
+    if &data[4..8] == b"INVC"
+        && matches!(mismatched, Err(Error::IdentifierMismatch { .. }))
+        && matches!(mismatched_batch, Err(Error::IdentifierMismatch { .. }))
+        && invoices.from_batch_bytes(&batch).unwrap()[0].number == 42
+        && receipt.number == 7
+    {
+        println!("Hello world! {}", receipt.total);
+    } else {
+        println!("Goodbye cruel world! {}", receipt.total);
+    }
+}
@@ -0,0 +1,75 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt, Reflect},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct DeviceV1 {
+    #[lean_buffer(id = 0)]
+    name: String,
+    #[lean_buffer(id = 1)]
+    firmware: String,
+    #[lean_buffer(id = 2)]
+    location: String,
+}
+
+// Fields may be reordered, slots follow the ids,
+// a removed field reserves its id, so it is never reused,
+// and a renamed field is still found by its old name.
+#[derive(LeanBufferWrite)]
+#[lean_buffer(deprecated = 1)]
+struct Device {
+    #[lean_buffer(id = 3)]
+    serial: u32,
+    #[lean_buffer(id = 2, alias = "location")]
+    room: String,
+    #[lean_buffer(id = 0)]
+    name: String,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod device_v1_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/DeviceV1_lb_gen.rs"));
+}
+
+mod device_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Device_lb_gen.rs"));
+}
+
+fn main() {
+    let e1 = DeviceV1 {
+        name: "thermostat".to_string(),
+        firmware: "1.0.2".to_string(),
+        location: "hallway".to_string(),
+    };
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten, with the old layout
+    let data = a1.to_bytes();
+
+    // inflate, with the new layout
+    let factory = Factory::<Device> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Device>>;
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.name == "thermostat"
+        && resurrected_e1.room == "hallway"
+        && resurrected_e1.serial == 0
+        && Device::field("location").map(|f| f.name) == Some("room")
+    {
+        println!("Hello world! {}", resurrected_e1.name);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.name);
+    }
+}
@@ -0,0 +1,68 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBuffer,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// No `include!` needed, the impls are expanded in place
+#[derive(LeanBuffer)]
+struct Coordinate {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(LeanBuffer)]
+#[lean_buffer(inspect)]
+struct Landmark {
+    name: String,
+    location: Coordinate,
+}
+
+fn main() {
+    let factory = Factory::<Landmark> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Landmark>>;
+    let mut e1 = f.new_object();
+
+    e1.name = "lighthouse".to_string();
+    e1.location = Coordinate {
+        latitude: 52.37,
+        longitude: 4.89,
+    };
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    // a type declared in a function body works alike
+    #[derive(LeanBuffer)]
+    struct Visit {
+        count: u32,
+    }
+
+    let visit_data = Visit { count: 3 }.to_bytes();
+    let visit = Factory::<Visit> {
+        phantom_data: std::marker::PhantomData,
+    }
+    .from_bytes(&visit_data)
+    .unwrap();
+
+    // `inspect` left a copy of what was expanded
+    let inspected = std::path::Path::new(env!("OUT_DIR")).join("Landmark_lb_inspect.rs");
+
+    if resurrected_e1.name == "lighthouse"
+        && resurrected_e1.location.longitude == 4.89
+        && inspected.exists()
+        && visit.count == 3
+    {
+        println!("Hello world! {}", resurrected_e1.name);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.name);
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Sensor {
+    name: String,
+    metadata: HashMap<String, f64>,
+    labels: BTreeMap<u32, String>,
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Sensor_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Sensor> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Sensor>>;
+    let mut e1 = f.new_object();
+
+    e1.name = "thermometer".to_string();
+    e1.metadata = HashMap::from([
+        ("offset".to_string(), -0.5),
+        ("gain".to_string(), 1.02),
+        ("calibrated".to_string(), 1.0),
+    ]);
+    e1.labels = BTreeMap::from([(7, "kitchen".to_string()), (3, "attic".to_string())]);
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // fetch a single entry, without inflating the whole map
+    let table = f.root_table(&data, &Default::default()).unwrap();
+    let gain = Sensor::lookup_metadata(&table, "gain").unwrap();
+    let missing = Sensor::lookup_labels(&table, 5).unwrap();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if gain == Some(1.02)
+        && missing.is_none()
+        && resurrected_e1.metadata.len() == 3
+        && resurrected_e1.metadata["offset"] == -0.5
+        && resurrected_e1.labels[&3] == "attic"
+        && resurrected_e1.labels[&7] == "kitchen"
+    {
+        println!("Hello world! {}", resurrected_e1.name);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.name);
+    }
+}
@@ -1,9 +1,7 @@
 use std::rc::Rc;
 
-use flatbuffers::FlatBufferBuilder;
 use lean_buffer::{
     macros::LeanBufferWrite,
-    macros::LeanBufferRaw,
     traits::{AdapterExt, Factory, FactoryExt},
 };
 
@@ -58,8 +56,6 @@ struct EntityMixed {
 include!(concat!(env!("OUT_DIR"), "/EntityMixed_lb_gen.rs"));
 
 fn main() {
-    let mut builder = FlatBufferBuilder::new();
-
     let factory = Factory::<EntityMixed> {
         phantom_data: std::marker::PhantomData,
     };
@@ -72,20 +68,14 @@ fn main() {
     let a1 = Box::new(e1) as Box<dyn AdapterExt>;
 
     // flatten
-    a1.flatten(&mut builder);
-    let data = builder.finished_data();
+    let data = a1.to_bytes();
 
     // inflate
-    let first_offset: usize = data[0].into();
-
-    unsafe {
-        let mut table = flatbuffers::Table::new(data, first_offset);
-        let resurrected_e1 = f.inflate(&mut table);
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
 
-        if resurrected_e1.t_i64 == e1_t_i64 {
-            println!("Hello world! {}", resurrected_e1.t_i64);
-        } else {
-            println!("Goodbye cruel world! {}", resurrected_e1.t_i64);
-        }
+    if resurrected_e1.t_i64 == e1_t_i64 {
+        println!("Hello world! {}", resurrected_e1.t_i64);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.t_i64);
     }
 }
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+// Fields may share their names with the locals of the generated code
+#[derive(LeanBufferWrite)]
+struct Label {
+    text: String,
+}
+
+#[derive(LeanBufferWrite)]
+#[lean_buffer(default)]
+struct Builderish {
+    builder: u32,
+    table: u16,
+    default_object: String,
+    wip_offset_unfinished: Vec<u8>,
+    a: [u8; 2],
+    n: Label,
+    inner: Option<String>,
+    key: BTreeMap<u32, u32>,
+    r#loop: BTreeMap<u32, u32>,
+    r#ref: Vec<Label>,
+}
+
+impl Default for Builderish {
+    fn default() -> Self {
+        Builderish {
+            builder: 7,
+            table: 0,
+            default_object: "default".to_string(),
+            wip_offset_unfinished: vec![],
+            a: [0; 2],
+            n: Label {
+                text: "n".to_string(),
+            },
+            inner: None,
+            key: BTreeMap::new(),
+            r#loop: BTreeMap::new(),
+            r#ref: vec![],
+        }
+    }
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod label_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Label_lb_gen.rs"));
+}
+
+mod builderish_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Builderish_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Builderish> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Builderish>>;
+    let mut e1 = f.new_object();
+
+    e1.table = 3;
+    e1.wip_offset_unfinished = vec![1, 2];
+    e1.a = [4, 5];
+    e1.n.text = "nested".to_string();
+    e1.inner = Some("inner".to_string());
+    e1.key.insert(1, 2);
+    e1.r#loop.insert(3, 4);
+    e1.r#ref = vec![Label {
+        text: "ref".to_string(),
+    }];
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.builder == 7
+        && resurrected_e1.table == 3
+        && resurrected_e1.default_object == "default"
+        && resurrected_e1.wip_offset_unfinished == vec![1, 2]
+        && resurrected_e1.a == [4, 5]
+        && resurrected_e1.n.text == "nested"
+        && resurrected_e1.inner.as_deref() == Some("inner")
+        && resurrected_e1.key.get(&1) == Some(&2)
+        && resurrected_e1.r#loop.get(&3) == Some(&4)
+        && resurrected_e1.r#ref[0].text == "ref"
+    {
+        println!("Hello world! {}", resurrected_e1.default_object);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.default_object);
+    }
+}
@@ -0,0 +1,102 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Address {
+    street: String,
+    number: u16,
+}
+
+#[derive(LeanBufferWrite)]
+struct Customer {
+    name: String,
+    address: Address,
+}
+
+#[derive(LeanBufferWrite)]
+struct OrderLine {
+    sku: String,
+    quantity: u32,
+}
+
+#[derive(LeanBufferWrite)]
+struct Order {
+    id: u64,
+    customer: Customer,
+    delivery: Address,
+    lines: Vec<OrderLine>,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod address_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Address_lb_gen.rs"));
+}
+
+mod customer_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Customer_lb_gen.rs"));
+}
+
+mod order_line_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/OrderLine_lb_gen.rs"));
+}
+
+mod order_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Order_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Order> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Order>>;
+    let mut e1 = f.new_object();
+
+    e1.id = 0x1337833F;
+    e1.customer.name = "Ada".to_string();
+    e1.customer.address.street = "Analytical Engine Lane".to_string();
+    e1.customer.address.number = 1843;
+    e1.delivery.number = 42;
+    e1.lines = vec![
+        OrderLine {
+            sku: "BRASS-GEAR".to_string(),
+            quantity: 12,
+        },
+        OrderLine {
+            sku: "PUNCH-CARD".to_string(),
+            quantity: 500,
+        },
+    ];
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.customer.address.number == 1843
+        && resurrected_e1.customer.address.street == "Analytical Engine Lane"
+        && resurrected_e1.customer.name == "Ada"
+        && resurrected_e1.delivery.number == 42
+        && resurrected_e1.lines.len() == 2
+        && resurrected_e1.lines[1].sku == "PUNCH-CARD"
+        && resurrected_e1.lines[1].quantity == 500
+    {
+        println!("Hello world! {}", resurrected_e1.customer.name);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.customer.name);
+    }
+}
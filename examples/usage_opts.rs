@@ -1,6 +1,5 @@
 use std::rc::Rc;
 
-use flatbuffers::FlatBufferBuilder;
 use lean_buffer::{
     macros::LeanBufferWrite,
     macros::LeanBufferRaw,
@@ -27,6 +26,7 @@ struct EntityOptions {
     t_opt_float: Option<f32>,
 }
 
+#[allow(dead_code)]
 #[derive(LeanBufferRaw)]
 struct EntityOptionsRaw {
     t_opt_u64: Option<u64>,
@@ -37,34 +37,71 @@ struct EntityOptionsRaw {
 include!(concat!(env!("OUT_DIR"), "/EntityOptions_lb_gen.rs"));
 
 fn main() {
-    let mut builder = FlatBufferBuilder::new();
-
     let factory = Factory::<EntityOptions> {
         phantom_data: std::marker::PhantomData,
     };
     let f = Rc::new(factory) as Rc<dyn FactoryExt<EntityOptions>>;
     let mut e1 = f.new_object();
 
-    let v = Some(64);
-    e1.t_opt_i64 = v;
+    // values that equal the default are present too
+    e1.t_opt_u64 = Some(0);
+    e1.t_opt_i64 = Some(0);
+    e1.t_opt_u32 = Some(0);
+    e1.t_opt_i32 = Some(0);
+    e1.t_opt_char = Some('\0');
+    e1.t_opt_u16 = Some(0);
+    e1.t_opt_i16 = Some(0);
+    e1.t_opt_u8 = Some(0);
+    e1.t_opt_i8 = Some(0);
+    e1.t_opt_bool = Some(false);
+    e1.t_opt_string = Some("".to_string());
+    e1.t_opt_double = Some(0.0);
+    e1.t_opt_float = Some(0.0);
 
     let a1 = Box::new(e1) as Box<dyn AdapterExt>;
 
     // flatten
-    a1.flatten(&mut builder);
-    let data = builder.finished_data();
+    let data = a1.to_bytes();
+
+    // and absent ones stay absent
+    let a2 = Box::new(f.new_object()) as Box<dyn AdapterExt>;
+    let empty_data = a2.to_bytes();
 
     // inflate
-    let first_offset: usize = data[0].into();
+    let e1 = f.from_bytes(&data).unwrap();
+    let e2 = f.from_bytes(&empty_data).unwrap();
+
+    let present = e1.t_opt_u64 == Some(0)
+        && e1.t_opt_i64 == Some(0)
+        && e1.t_opt_u32 == Some(0)
+        && e1.t_opt_i32 == Some(0)
+        && e1.t_opt_char == Some('\0')
+        && e1.t_opt_u16 == Some(0)
+        && e1.t_opt_i16 == Some(0)
+        && e1.t_opt_u8 == Some(0)
+        && e1.t_opt_i8 == Some(0)
+        && e1.t_opt_bool == Some(false)
+        && e1.t_opt_string.as_deref() == Some("")
+        && e1.t_opt_double == Some(0.0)
+        && e1.t_opt_float == Some(0.0);
 
-    unsafe {
-        let mut table = flatbuffers::Table::new(data, first_offset);
-        let resurrected_e1 = f.inflate(&mut table);
+    let absent = e2.t_opt_u64.is_none()
+        && e2.t_opt_i64.is_none()
+        && e2.t_opt_u32.is_none()
+        && e2.t_opt_i32.is_none()
+        && e2.t_opt_char.is_none()
+        && e2.t_opt_u16.is_none()
+        && e2.t_opt_i16.is_none()
+        && e2.t_opt_u8.is_none()
+        && e2.t_opt_i8.is_none()
+        && e2.t_opt_bool.is_none()
+        && e2.t_opt_string.is_none()
+        && e2.t_opt_double.is_none()
+        && e2.t_opt_float.is_none();
 
-        if resurrected_e1.t_opt_i64 == Some(64) {
-            println!("Hello world!");
-        } else {
-            println!("Goodbye cruel world!");
-        }
+    if present && absent {
+        println!("Hello world!");
+    } else {
+        println!("Goodbye cruel world!");
     }
 }
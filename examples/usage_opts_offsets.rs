@@ -0,0 +1,72 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Avatar {
+    url: String,
+}
+
+// An absent value leaves its slot out of the vtable,
+// so `Some(vec![])` and `None` remain distinguishable
+#[derive(LeanBufferWrite)]
+struct Profile {
+    nickname: Option<String>,
+    scores: Option<Vec<u32>>,
+    tags: Option<Vec<String>>,
+    avatar: Option<Avatar>,
+    checksum: Option<[u8; 4]>,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod avatar_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Avatar_lb_gen.rs"));
+}
+
+mod profile_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Profile_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Profile> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Profile>>;
+    let mut e1 = f.new_object();
+
+    e1.nickname = Some("".to_string());
+    e1.scores = Some(vec![]);
+    e1.avatar = Some(Avatar {
+        url: "https://example.com/a.png".to_string(),
+    });
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.nickname.as_deref() == Some("")
+        && resurrected_e1.scores == Some(vec![])
+        && resurrected_e1.tags.is_none()
+        && resurrected_e1.checksum.is_none()
+        && resurrected_e1
+            .avatar
+            .is_some_and(|a| a.url == "https://example.com/a.png")
+    {
+        println!("Hello world!");
+    } else {
+        println!("Goodbye cruel world!");
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use flatbuffers::InvalidFlatbuffer;
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Error, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Login {
+    attempts: u32,
+    #[lean_buffer(required)]
+    user: String,
+    #[lean_buffer(required)]
+    labels: BTreeMap<u32, u32>,
+}
+
+// The same layout, without the required field
+#[derive(LeanBufferWrite)]
+struct Anonymous {
+    attempts: u32,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod login_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Login_lb_gen.rs"));
+}
+
+mod anonymous_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Anonymous_lb_gen.rs"));
+}
+
+fn main() {
+    let e1 = Login {
+        attempts: 1,
+        user: "".to_string(),
+        // a zero key or value is elided in its entry, like any other scalar
+        labels: BTreeMap::from([(0, 0), (1, 2)]),
+    };
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    let a2 = Box::new(Anonymous { attempts: 2 }) as Box<dyn AdapterExt>;
+    let anonymous_data = a2.to_bytes();
+
+    // inflate
+    let factory = Factory::<Login> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Login>>;
+
+    let resurrected_e1 = f.from_bytes(&data);
+
+    // an empty string is present, an absent one is rejected,
+    // already by the verifier, or else by `try_inflate`
+    let rejected = f.from_bytes(&anonymous_data);
+    let unverified = unsafe {
+        let root = flatbuffers::read_scalar::<u32>(&anonymous_data) as usize;
+        let mut table = flatbuffers::Table::new(&anonymous_data, root);
+        f.try_inflate(&mut table)
+    };
+
+    match (resurrected_e1, rejected, unverified) {
+        (
+            Ok(login),
+            Err(Error::Invalid(InvalidFlatbuffer::MissingRequiredField { required: "user", .. })),
+            Err(Error::MissingField { field: "user" }),
+        ) if login.attempts == 1 && login.labels == BTreeMap::from([(0, 0), (1, 2)]) => {
+            println!("Hello world! {}", Error::MissingField { field: "user" });
+        }
+        _ => println!("Goodbye cruel world!"),
+    }
+}
@@ -0,0 +1,59 @@
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Instant;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+// Skipped fields are not written, and take no slot
+#[derive(LeanBufferWrite)]
+struct Session {
+    user: String,
+    #[lean_buffer(skip)]
+    cache: Vec<String>,
+    #[lean_buffer(skip, default_fn = "Instant::now")]
+    opened: Instant,
+    #[lean_buffer(skip)]
+    marker: PhantomData<u8>,
+    visits: u32,
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Session_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Session> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Session>>;
+    let mut e1 = f.new_object();
+
+    e1.user = "Alan".to_string();
+    e1.cache = vec!["expensive".to_string()];
+    e1.visits = 7;
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if resurrected_e1.user == "Alan"
+        && resurrected_e1.visits == 7
+        && resurrected_e1.cache.is_empty()
+        && resurrected_e1.opened.elapsed().as_secs() < 60
+        && resurrected_e1.marker == PhantomData
+    {
+        println!("Hello world! {}", resurrected_e1.user);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.user);
+    }
+}
@@ -0,0 +1,53 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Meters(f64);
+
+#[derive(LeanBufferWrite)]
+struct Pair(u32, String, Meters);
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod meters_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Meters_lb_gen.rs"));
+}
+
+mod pair_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Pair_lb_gen.rs"));
+}
+
+fn main() {
+    let factory = Factory::<Pair> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Pair>>;
+    let mut e1 = f.new_object();
+
+    e1.0 = 0x1337833F;
+    e1.1 = "furlong".to_string();
+    e1.2 = Meters(201.168);
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let Pair(id, unit, Meters(meters)) = f.from_bytes(&data).unwrap();
+
+    if id == 0x1337833F && unit == "furlong" && meters == 201.168 {
+        println!("Hello world! {}", unit);
+    } else {
+        println!("Goodbye cruel world! {}", unit);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{rc::Rc, sync::mpsc};
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Ping {
+    sequence: u32,
+}
+
+#[derive(LeanBufferWrite)]
+enum Message {
+    #[lean_buffer(fallback)]
+    Unknown,
+    Ping(Ping),
+    Data { channel: u16, payload: Vec<u8> },
+    Text(String, bool),
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod ping_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Ping_lb_gen.rs"));
+}
+
+mod message_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Message_lb_gen.rs"));
+}
+
+fn main() {
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+
+    for message in [
+        Message::Ping(Ping { sequence: 7 }),
+        Message::Data {
+            channel: 3,
+            payload: vec![0x8, 0x3, 0x3, 0xF],
+        },
+        Message::Text("over and out".to_string(), true),
+        Message::Unknown,
+    ] {
+        let a1 = Box::new(message) as Box<dyn AdapterExt>;
+
+        // flatten
+        tx.send(a1.to_bytes()).unwrap();
+    }
+    drop(tx);
+
+    let factory = Factory::<Message> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Message>>;
+
+    let mut received = 0;
+    for data in rx {
+        // inflate
+        let resurrected = f.from_bytes(&data).unwrap();
+
+        let expected = match resurrected {
+            Message::Ping(p) => p.sequence == 7,
+            Message::Data { channel, payload } => channel == 3 && payload == vec![0x8, 0x3, 0x3, 0xF],
+            Message::Text(text, over) => text == "over and out" && over,
+            Message::Unknown => true,
+        };
+        if expected {
+            received += 1;
+        }
+    }
+
+    if received == 4 {
+        println!("Hello world! {}", received);
+    } else {
+        println!("Goodbye cruel world! {}", received);
+    }
+}
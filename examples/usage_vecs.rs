@@ -1,6 +1,5 @@
 use std::rc::Rc;
 
-use flatbuffers::FlatBufferBuilder;
 use lean_buffer::{
     macros::LeanBufferWrite,
     macros::LeanBufferRaw,
@@ -32,8 +31,6 @@ struct EntityVecs {
 include!(concat!(env!("OUT_DIR"), "/EntityVecs_lb_gen.rs"));
 
 fn main() {
-    let mut builder = FlatBufferBuilder::new();
-
     let factory = Factory::<EntityVecs> {
         phantom_data: std::marker::PhantomData,
     };
@@ -46,20 +43,14 @@ fn main() {
     let a1 = Box::new(e1) as Box<dyn AdapterExt>;
 
     // flatten
-    a1.flatten(&mut builder);
-    let data = builder.finished_data();
+    let data = a1.to_bytes();
 
     // inflate
-    let first_offset: usize = data[0].into();
-
-    unsafe {
-        let mut table = flatbuffers::Table::new(data, first_offset);
-        let resurrected_e1 = f.inflate(&mut table);
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
 
-        if resurrected_e1.t_vec_i64 == vec![0x8, 0x3, 0x3, 0xF] {
-            println!("Hello world!");
-        } else {
-            println!("Goodbye cruel world!");
-        }
+    if resurrected_e1.t_vec_i64 == vec![0x8, 0x3, 0x3, 0xF] {
+        println!("Hello world!");
+    } else {
+        println!("Goodbye cruel world!");
     }
 }
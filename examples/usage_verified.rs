@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use flatbuffers::{FlatBufferBuilder, InvalidFlatbuffer, VerifierOptions};
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Error, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite, PartialEq, Debug)]
+enum Level {
+    Low,
+    High,
+}
+
+#[derive(LeanBufferWrite, PartialEq, Debug)]
+struct Note {
+    text: String,
+}
+
+#[derive(LeanBufferWrite, PartialEq, Debug)]
+enum Payload {
+    Empty,
+    Note(Note),
+    Levels { levels: Vec<Level> },
+}
+
+#[derive(LeanBufferWrite, PartialEq, Debug)]
+struct Packet {
+    #[lean_buffer(required)]
+    topic: String,
+    payload: Payload,
+    counters: BTreeMap<String, u64>,
+    digest: [u8; 4],
+    id: u128,
+    notes: Vec<Note>,
+}
+
+// Every generated file imports its own dependencies,
+// so each one is included in a separate module.
+mod level_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Level_lb_gen.rs"));
+}
+
+mod note_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Note_lb_gen.rs"));
+}
+
+mod payload_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Payload_lb_gen.rs"));
+}
+
+mod packet_gen {
+    use super::*;
+    include!(concat!(env!("OUT_DIR"), "/Packet_lb_gen.rs"));
+}
+
+fn main() {
+    let mut builder = FlatBufferBuilder::new();
+
+    let factory = Factory::<Packet> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Packet>>;
+
+    let packets = [
+        Packet {
+            topic: "telemetry".to_string(),
+            payload: Payload::Levels {
+                levels: vec![Level::High, Level::Low],
+            },
+            counters: BTreeMap::from([("sent".to_string(), 3), ("lost".to_string(), 0)]),
+            digest: [0xde, 0xad, 0xbe, 0xef],
+            id: u128::MAX,
+            notes: vec![Note {
+                text: "first".to_string(),
+            }],
+        },
+        Packet {
+            topic: "".to_string(),
+            payload: Payload::Empty,
+            counters: BTreeMap::new(),
+            digest: [0; 4],
+            id: 0,
+            notes: vec![],
+        },
+    ];
+
+    // verified, without `unsafe`
+    let round_trips = packets.iter().all(|p| {
+        p.flatten(&mut builder);
+        f.try_inflate_bytes(builder.finished_data()).as_ref() == Ok(p)
+    });
+
+    let a1 = Box::new(Packet {
+        topic: "nested".to_string(),
+        payload: Payload::Note(Note {
+            text: "deep".to_string(),
+        }),
+        ..f.new_object()
+    }) as Box<dyn AdapterExt>;
+    a1.flatten(&mut builder);
+    let data = builder.finished_data();
+
+    // malformed buffers are rejected, instead of being read out of bounds
+    let truncated = f.try_inflate_bytes(&data[..data.len() / 2]);
+    let mut corrupted = data.to_vec();
+    corrupted[0] = 0xff;
+    let corrupted = f.try_inflate_bytes(&corrupted);
+    let too_deep = f.try_inflate_bytes_with_opts(
+        data,
+        &VerifierOptions {
+            max_depth: 2,
+            ..Default::default()
+        },
+    );
+
+    if round_trips
+        && f.try_inflate_bytes(data).is_ok()
+        && matches!(truncated, Err(Error::Invalid(_)))
+        && matches!(corrupted, Err(Error::Invalid(_)))
+        && too_deep == Err(Error::Invalid(InvalidFlatbuffer::DepthLimitReached))
+    {
+        println!("Hello world! {}", packets[0].topic);
+    } else {
+        println!("Goodbye cruel world! {:?}", truncated);
+    }
+}
@@ -0,0 +1,57 @@
+use std::rc::Rc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+#[derive(LeanBufferWrite)]
+struct Record {
+    id: u128,
+    delta: i128,
+    len: usize,
+    offset: isize,
+    capacity: Option<usize>,
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Record_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Record> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Record>>;
+    let mut e1 = f.new_object();
+
+    e1.id = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+    e1.delta = i128::MIN;
+    e1.len = 1 << 40;
+    e1.offset = -42;
+    e1.capacity = Some(4096);
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    // `usize` and `isize` are stored as 64-bit values,
+    // reading one that does not fit on this target is an error
+    let resurrected_e1 = f.from_bytes(&data).expect("overflow");
+
+    if resurrected_e1.id == 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210
+        && resurrected_e1.delta == i128::MIN
+        && resurrected_e1.len == 1 << 40
+        && resurrected_e1.offset == -42
+        && resurrected_e1.capacity == Some(4096)
+    {
+        println!("Hello world! {:x}", resurrected_e1.id);
+    } else {
+        println!("Goodbye cruel world! {:x}", resurrected_e1.id);
+    }
+}
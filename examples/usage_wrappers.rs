@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use lean_buffer::{
+    macros::LeanBufferWrite,
+    traits::{AdapterExt, Factory, FactoryExt},
+};
+
+// Don't panic when you see this false positive warning:
+// proc macro `LeanBufferWrite` not expanded: proc macro not found in the built dylib
+// Just check if the generated file can be located.
+// Wrappers are encoded as what they wrap
+#[derive(LeanBufferWrite)]
+struct Model {
+    name: Arc<str>,
+    description: Cow<'static, str>,
+    weights: Box<[u8]>,
+    revision: Rc<u32>,
+    parent: Option<Box<Model>>,
+}
+
+// Either copy this file from your project, or use the name convention
+// `<struct name>_lb_gen.rs` to include the generated file.
+include!(concat!(env!("OUT_DIR"), "/Model_lb_gen.rs"));
+
+fn main() {
+    let factory = Factory::<Model> {
+        phantom_data: std::marker::PhantomData,
+    };
+    let f = Rc::new(factory) as Rc<dyn FactoryExt<Model>>;
+    let mut e1 = f.new_object();
+
+    e1.name = Arc::from("resnet");
+    e1.description = Cow::Borrowed("a residual network");
+    e1.weights = Box::from([0xde, 0xad, 0xbe, 0xef]);
+    e1.revision = Rc::new(50);
+    e1.parent = Some(Box::new(Model {
+        name: Arc::from("lenet"),
+        description: Cow::Borrowed(""),
+        weights: Box::from([]),
+        revision: Rc::new(5),
+        parent: None,
+    }));
+
+    let a1 = Box::new(e1) as Box<dyn AdapterExt>;
+
+    // flatten
+    let data = a1.to_bytes();
+
+    // inflate
+    let resurrected_e1 = f.from_bytes(&data).unwrap();
+
+    if &*resurrected_e1.name == "resnet"
+        && resurrected_e1.description == "a residual network"
+        && *resurrected_e1.weights == [0xde, 0xad, 0xbe, 0xef]
+        && *resurrected_e1.revision == 50
+        && resurrected_e1
+            .parent
+            .is_some_and(|p| &*p.name == "lenet" && *p.revision == 5 && p.parent.is_none())
+    {
+        println!("Hello world! {}", resurrected_e1.name);
+    } else {
+        println!("Goodbye cruel world! {}", resurrected_e1.name);
+    }
+}
@@ -1,13 +1,18 @@
 use std::{
+    collections::HashMap,
     env, fs,
     path::{Path, PathBuf},
 };
 
-use darling::{ast, FromDeriveInput, FromField};
+use darling::{ast, util::Override, FromDeriveInput, FromField, FromVariant};
 use genco::{
     prelude::{rust, Rust},
-    quote, Tokens,
+    quote,
+    tokens::quoted,
+    Tokens,
 };
+use quote::ToTokens;
+use syn::ext::IdentExt;
 
 use genco::fmt;
 
@@ -46,14 +51,84 @@ pub fn tokens_to_pretty_string(tokens: &rust::Tokens) -> String {
 }
 
 #[derive(Debug, FromDeriveInput)]
-#[darling(attributes(lean_buffer_internal), supports(struct_any))]
+#[darling(
+    attributes(lean_buffer),
+    supports(struct_any, enum_any),
+    and_then = Self::prepare_fields
+)]
 pub struct InputReceiver {
     ident: syn::Ident,
     generics: syn::Generics,
-    pub data: ast::Data<(), FieldReceiver>,
+    pub data: ast::Data<VariantReceiver, FieldReceiver>,
+    /// Type aliases the fields may use, e.g. `#[lean_buffer(aliases(Id = "u64"))]`
+    /// for `type Id = u64;`, since a derive cannot look them up
+    #[darling(default)]
+    aliases: HashMap<syn::Ident, syn::Type>,
+    /// Ids of removed fields, whose slots stay reserved, e.g. `#[lean_buffer(deprecated = 2)]`
+    #[darling(default, multiple)]
+    deprecated: Vec<u16>,
+    /// Starts from the `Default` impl, e.g. `#[lean_buffer(default)]`,
+    /// scalars that equal their default are left out, and restored from it
+    #[darling(default)]
+    default: bool,
+    /// Written on finish and checked before inflating, e.g. `#[lean_buffer(file_identifier = "ENTY")]`,
+    /// or derived from the name of the type with `#[lean_buffer(file_identifier)]`
+    #[darling(default)]
+    file_identifier: Option<Override<String>>,
+    /// Also writes what the inline derive returns to `<name>_lb_inspect.rs` in `OUT_DIR`,
+    /// e.g. `#[lean_buffer(inspect)]`
+    #[darling(default)]
+    pub inspect: bool,
+    /// Refers to the type by its bare name, since the impls are expanded next to it,
+    /// e.g. in a function body, where `self::` would name the enclosing module
+    #[darling(skip)]
+    inline: bool,
 }
 
 impl InputReceiver {
+    fn prepare_fields(mut self) -> darling::Result<Self> {
+        if let Some(Override::Explicit(id)) = &self.file_identifier {
+            if id.len() != 4 {
+                return Err(darling::Error::custom(format!(
+                    "The file identifier `{}` must be exactly 4 bytes long",
+                    id
+                ))
+                .with_span(&self.ident));
+            }
+        }
+        let is_union = self.is_union();
+        match &mut self.data {
+            ast::Data::Struct(fields) => {
+                number_fields(fields);
+                assign_offsets(fields, &self.deprecated)?;
+                for f in fields.fields.iter_mut() {
+                    path_visitor::resolve_aliases(&mut f.ty, &self.aliases);
+                    f.struct_default = self.default && f.default.is_none();
+                }
+                check_required(fields)?;
+                check_defaults(fields)?;
+                check_names(fields)?;
+            }
+            ast::Data::Enum(_) if self.default => {
+                return Err(darling::Error::custom(
+                    "Not supported: `default` on an enum, use it on the fields of a variant",
+                )
+                .with_span(&self.ident));
+            }
+            ast::Data::Enum(variants) => {
+                for f in variants.iter_mut().flat_map(|v| v.fields.fields.iter_mut()) {
+                    path_visitor::resolve_aliases(&mut f.ty, &self.aliases);
+                }
+                for v in variants.iter() {
+                    check_required(&v.fields)?;
+                    check_defaults(&v.fields)?;
+                }
+                check_variants(&self.ident, variants, is_union)?;
+            }
+        }
+        Ok(self)
+    }
+
     // monomorph
     fn write<F: Fn(&mut rust::Tokens) -> String>(
         &mut self,
@@ -106,6 +181,26 @@ impl InputReceiver {
         }
     }
 
+    pub fn write_inspect_to_out_dir(&mut self) {
+        self.inline = true;
+        if let Some(out_dir) = env::var_os("OUT_DIR") {
+            let dest_path =
+                Path::new(&out_dir).join(format!("{}_lb_inspect.rs", self.ident.to_string().clone()));
+            self.write(&dest_path, None, None, None, |t|tokens_to_pretty_string(t));
+        } else {
+            panic!("Missing OUT_DIR environment variable, add a `build.rs` with at least an empty `fn main` to the root of your project, or drop `inspect`");
+        }
+    }
+
+    /// The generated impls, to be returned by the derive itself instead of written to a file,
+    /// their imports are scoped to an anonymous const, so they cannot clash with the caller's
+    pub fn generate_inline(&mut self) -> String {
+        self.inline = true;
+        let tokens = &mut rust::Tokens::new();
+        self.generate_tokens(tokens, None, None, None);
+        format!("const _: () = {{\n{}}};", tokens_to_string(tokens))
+    }
+
     pub fn generate_tokens(
         &self,
         tokens: &mut rust::Tokens,
@@ -113,225 +208,1505 @@ impl InputReceiver {
         factory_name: Option<String>,
         as_alias: Option<String>,
     ) {
-        tokens.append(
-            self.generate_factory(
-                factory_module
-                    .unwrap_or("lean_buffer::traits".to_string())
-                    .as_str(),
-                factory_name.unwrap_or("Factory".to_string()).as_str(),
-                as_alias,
-            ),
+        let factory = &Self::factory_import(
+            factory_module
+                .unwrap_or("lean_buffer::traits".to_string())
+                .as_str(),
+            factory_name.unwrap_or("Factory".to_string()).as_str(),
+            as_alias,
         );
-        tokens.append(self.generate_table_adapter());
+        if self.is_union() {
+            tokens.append(self.generate_union_factory(factory));
+            tokens.append(self.generate_table_adapter());
+            tokens.append(self.generate_union_lean_buffer());
+        } else if self.data.is_enum() {
+            tokens.append(self.generate_enum_factory(factory));
+            tokens.append(self.generate_enum_table_adapter());
+            tokens.append(self.generate_enum_lean_buffer());
+        } else {
+            tokens.append(self.generate_factory(factory));
+            tokens.append(self.generate_table_adapter());
+            tokens.append(self.generate_lean_buffer(factory));
+            tokens.append(self.generate_map_lookups());
+            tokens.append(self.generate_reflect());
+        }
     }
 
-    fn generate_factory(
-        &self,
+    /// The explicit file identifier, or one derived from the name of the type
+    fn get_file_identifier(&self) -> Option<String> {
+        match self.file_identifier.as_ref()? {
+            Override::Explicit(id) => Some(id.clone()),
+            Override::Inherit => Some(derive_file_identifier(&self.ident.to_string())),
+        }
+    }
+
+    fn generate_file_identifier_const(&self) -> Option<Tokens<Rust>> {
+        self.get_file_identifier()
+            .map(|id| quote!(const FILE_IDENTIFIER: Option<&'static str> = Some($(quoted(id)));))
+    }
+
+    /// Hands `LeanBuffer::FILE_IDENTIFIER` to `flatten`, which has no `LeanBuffer` bound
+    fn generate_file_identifier_fn(&self) -> Option<Tokens<Rust>> {
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        self.get_file_identifier().map(|_| {
+            quote! {
+              fn file_identifier(&self) -> Option<&'static str> {
+                <Self as $lean_buffer>::FILE_IDENTIFIER
+              }
+            }
+        })
+    }
+
+    /// e.g. `self::Entity`, or `Entity` when expanded inline
+    fn get_entity(&self) -> Tokens<Rust> {
+        let name = self.ident.to_string();
+        if self.inline {
+            quote!($name)
+        } else {
+            quote!($(rust::import("self", name)))
+        }
+    }
+
+    /// e.g. `<T: LeanBuffer>`, `self::Envelope<T>` and `where T: Clone`,
+    /// of `struct Envelope<T: LeanBuffer> where T: Clone`
+    fn get_generics(&self, entity: &Tokens<Rust>) -> (String, Tokens<Rust>, String) {
+        let generics = &self.get_bounded_generics();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        (
+            impl_generics.to_token_stream().to_string(),
+            quote!($entity$(ty_generics.to_token_stream().to_string())),
+            where_clause.to_token_stream().to_string(),
+        )
+    }
+
+    /// `T: LeanBuffer` does not imply what a vector of `T` needs,
+    /// so each type parameter that is the element of a vector field is bound further
+    fn get_bounded_generics(&self) -> syn::Generics {
+        let fields: Vec<&FieldReceiver> = match &self.data {
+            ast::Data::Struct(fields) => fields.iter().collect(),
+            ast::Data::Enum(variants) => variants.iter().flat_map(|v| v.fields.iter()).collect(),
+        };
+        let params: Vec<&syn::Ident> = self.generics.type_params().map(|p| &p.ident).collect();
+        let mut elements: Vec<&syn::Type> = vec![];
+        for ty in fields
+            .iter()
+            .filter(|f| !f.skip)
+            .flat_map(|f| path_visitor::get_vec_elements(&f.ty))
+        {
+            let is_param = matches!(ty, syn::Type::Path(p)
+                if p.qself.is_none() && p.path.get_ident().is_some_and(|i| params.contains(&i)));
+            if is_param && !elements.contains(&ty) {
+                elements.push(ty);
+            }
+        }
+
+        let mut generics = self.generics.clone();
+        for ty in elements {
+            let predicates = &mut generics.make_where_clause().predicates;
+            predicates.push(syn::parse_quote!(
+                for<'lb> flatbuffers::Vector<'lb, <#ty as lean_buffer::traits::LeanBuffer>::Verified>: flatbuffers::Verifiable
+            ));
+            predicates.push(syn::parse_quote!(
+                <<#ty as lean_buffer::traits::LeanBuffer>::Slot as flatbuffers::Push>::Output: 'static
+            ));
+        }
+        generics
+    }
+
+    fn factory_import(
         factory_module: &str,
         factory_name: &str,
         as_alias: Option<String>,
-    ) -> Tokens<Rust> {
+    ) -> rust::Import {
+        let factory = rust::import(factory_module, factory_name);
+        match as_alias {
+            Some(alias) => factory.with_module_alias(alias),
+            None => factory,
+        }
+    }
+
+    fn generate_factory(&self, factory: &rust::Import) -> Tokens<Rust> {
         let fields = self
             .data
             .as_ref()
             .take_struct()
-            .expect("Enums are not supported (yet)")
-            .fields;
+            .expect("Enums are not supported (yet)");
 
         let fb_table = &rust::import("flatbuffers", "Table");
 
-        let mut factory = rust::import(factory_module, factory_name);
-        if as_alias.is_some() {
-            factory = factory.with_module_alias(as_alias.unwrap());
-        }
-
         let factory_ext = &rust::import("lean_buffer::traits", "FactoryExt");
-        let entity = &rust::import("self", &self.ident.to_string());
+        let error = &rust::import("lean_buffer::traits", "Error");
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let path = &quote!($entity);
 
-        let destructured_props = fields.iter().map(|p| p.as_struct_property_default());
-        let assigned_props = fields
-            .iter()
-            .enumerate()
-            .map(|p| p.1.as_assigned_property(p.0 * 2 + 4));
+        let destructured = destructure(path, &fields);
+        let constructed = if self.default {
+            construct_from_default(path, &fields)
+        } else {
+            construct_default(path, &fields)
+        };
+        let assigned_props = generate_assigned_properties(&fields.fields);
 
         quote! {
-          impl $factory_ext<$entity> for $factory<$entity> {
-            fn inflate<'a>(&self, table: &mut $fb_table<'a>) -> $entity {
+          impl$impl_generics $factory_ext<$entity_type> for $factory<$entity_type> $where_clause {
+            fn try_inflate<'buf>(&self, table: &mut $fb_table<'buf>) -> Result<$entity_type, $error> {
               let mut object = self.new_object();
               // destructure
-              let $entity {
-                $(for f in &fields join (, ) => $(f.get_name()))
-              } = &mut object;
+              let $destructured = &mut object;
               unsafe {
-                $(for p in assigned_props join () => $(p))
+                $assigned_props
               }
-              object
+              Ok(object)
             }
 
-            fn new_object(&self) -> $entity {
-              $entity {
-                $(for p in destructured_props join (, ) => $(p))
-              }
+            fn new_object(&self) -> $entity_type {
+              $constructed
             }
           }
         }
     }
 
     fn generate_table_adapter(&self) -> Tokens<Rust> {
-        let entity = &rust::import("self", &self.ident.to_string());
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
         let bridge_trait = &rust::import("lean_buffer::traits", "AdapterExt");
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
         let flatbuffer_builder = &rust::import("flatbuffers", "FlatBufferBuilder");
+        let wip_offset = &rust::import("flatbuffers", "WIPOffset");
+        let table_finished = &rust::import("flatbuffers", "TableFinishedWIPOffset");
+
+        quote! {
+          impl$impl_generics $bridge_trait for $entity_type $where_clause {
+            fn flatten_table(&self, builder: &mut $flatbuffer_builder<'_>) -> $wip_offset<$table_finished> {
+              $lean_buffer::flatten_slot(self, builder)
+            }
+            $(self.generate_file_identifier_fn())
+          }
+        }
+    }
+
+    /// Writes the table without resetting or finishing the builder,
+    /// so the derived type can be nested in another derived type
+    fn generate_lean_buffer(&self, factory: &rust::Import) -> Tokens<Rust> {
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let factory_ext = &rust::import("lean_buffer::traits", "FactoryExt");
+        let error = &rust::import("lean_buffer::traits", "Error");
+        let flatbuffer_builder = &rust::import("flatbuffers", "FlatBufferBuilder");
+        let fb_table = &rust::import("flatbuffers", "Table");
+        let fuo = &rust::import("flatbuffers", "ForwardsUOffset");
+        let wip_offset = &rust::import("flatbuffers", "WIPOffset");
+        let table_finished = &rust::import("flatbuffers", "TableFinishedWIPOffset");
 
-        // TODO Box or Rc instances, and define a `fn get_fields(&self) -> Vec<Rc<clone>>`
         let fields = self
             .data
             .as_ref()
             .take_struct()
-            .expect("Enums are not supported (yet)")
-            .fields;
+            .expect("only called for structs");
 
-        let unnested_props: Vec<Tokens<Rust>> = fields
+        let verifier = &rust::import("flatbuffers", "Verifier");
+        let invalid = &rust::import("flatbuffers", "InvalidFlatbuffer");
+        let verified_table = &rust::import("lean_buffer::traits", "VerifiedTable");
+
+        let destructured = destructure(&quote!($entity), &fields);
+        let table = generate_table(&fields.fields);
+        let verified_fields = generate_verified_fields(&fields.fields);
+        let default_object = fields
             .iter()
-            .enumerate()
-            .map(|(i, p)| p.encode_flatten_unnested(i * 2 + 4))
+            .any(|f| f.get_elided_default().is_some() && f.struct_default)
+            .then(|| quote!(let default_object = <Self as Default>::default();));
+
+        quote! {
+          impl$impl_generics $lean_buffer for $entity_type $where_clause {
+            type Slot = $wip_offset<$table_finished>;
+            type Followed<'buf> = $fuo<$fb_table<'buf>>;
+            type Verified = $fuo<$verified_table<Self>>;
+            $(self.generate_file_identifier_const())
+
+            fn flatten_slot(&self, builder: &mut $flatbuffer_builder<'_>) -> Self::Slot {
+              let $destructured = self;
+              $default_object
+              $table
+            }
+
+            fn try_inflate_slot<'buf>(mut followed: $fb_table<'buf>) -> Result<Self, $error> {
+              let factory = $factory::<$entity_type> {
+                phantom_data: std::marker::PhantomData,
+              };
+              $factory_ext::<$entity_type>::try_inflate(&factory, &mut followed)
+            }
+
+            fn new_slot() -> Self {
+              let factory = $factory::<$entity_type> {
+                phantom_data: std::marker::PhantomData,
+              };
+              $factory_ext::<$entity_type>::new_object(&factory)
+            }
+
+            fn verify_table(v: &mut $verifier<'_, '_>, pos: usize, _variant: u8) -> Result<(), $invalid> {
+              v.visit_table(pos)?
+                $verified_fields
+                .finish();
+              Ok(())
+            }
+          }
+        }
+    }
+
+    fn generate_reflect(&self) -> Tokens<Rust> {
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let reflect = &rust::import("lean_buffer::traits", "Reflect");
+        let field_info = &rust::import("lean_buffer::traits", "FieldInfo");
+        let fields = self
+            .data
+            .as_ref()
+            .take_struct()
+            .expect("only called for structs");
+        let infos = slotted(&fields.fields).into_iter().map(|(offset, f)| {
+            quote! {
+                $field_info {
+                    name: $(quoted(f.get_wire_name())),
+                    aliases: &[$(for a in &f.alias join (, ) => $(quoted(a)))],
+                    id: $((offset - 4) / 2),
+                }
+            }
+        });
+
+        quote! {
+          impl$impl_generics $reflect for $entity_type $where_clause {
+            const FIELDS: &'static [$field_info] = &[
+              $(for i in infos join (,) => $i)
+            ];
+          }
+        }
+    }
+
+    /// Binary search for a single key in the key-sorted entries of each map field,
+    /// without inflating the whole map
+    fn generate_map_lookups(&self) -> Tokens<Rust> {
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let fb_table = &rust::import("flatbuffers", "Table");
+        let fuo = &rust::import("flatbuffers", "ForwardsUOffset");
+        let fvec = &rust::import("flatbuffers", "Vector");
+        let ordering = &rust::import("std::cmp", "Ordering");
+        let error = &rust::import("lean_buffer::traits", "Error");
+
+        let fields = self
+            .data
+            .as_ref()
+            .take_struct()
+            .expect("only called for structs");
+
+        let lookups: Vec<Tokens<Rust>> = slotted(&fields.fields)
+            .into_iter()
+            .filter_map(|(offset, f)| f.get_map_entry().map(|entry| (offset, f, entry)))
+            .map(|(offset, f, (key, value))| {
+                let (key_param, entry_key, key_ref) = match key.get_joined_type_name().as_str() {
+                    "String" => (
+                        quote!(&str),
+                        quote!(table.get::<$fuo<&str>>(4, None).unwrap_or("")),
+                        quote!(key),
+                    ),
+                    k => (
+                        quote!($k),
+                        quote!(table.get::<$k>(4, Some(0)).unwrap()),
+                        quote!(&key),
+                    ),
+                };
+                let value_type = value.get_type_name();
+                let value_default = value.as_default_value();
+                let value_assigned = value.as_assigned_property(6);
+
+                quote! {
+                    pub fn lookup_$(f.get_unraw_name())<'buf>(table: &$fb_table<'buf>, key: $key_param) -> Result<Option<$value_type>, $error> {
+                        let entries = match unsafe { table.get::<$fuo<$fvec<'buf, $fuo<$fb_table<'buf>>>>>($offset, None) } {
+                            Some(entries) => entries,
+                            None => return Ok(None),
+                        };
+                        let (mut low, mut high) = (0, entries.len());
+                        while low < high {
+                            let mid = low + (high - low) / 2;
+                            let table = entries.get(mid);
+                            let entry_key = unsafe { $entry_key };
+                            match entry_key.cmp($key_ref) {
+                                $ordering::Less => low = mid + 1,
+                                $ordering::Greater => high = mid,
+                                $ordering::Equal => {
+                                    let mut value = $value_default;
+                                    {
+                                        let $(value.get_binding()) = &mut value;
+                                        unsafe { $value_assigned }
+                                    }
+                                    return Ok(Some(value));
+                                }
+                            }
+                        }
+                        Ok(None)
+                    }
+                }
+            })
             .collect();
 
-        let mut props_unsorted: Vec<(usize, Tokens<Rust>)> = fields
+        if lookups.is_empty() {
+            return quote!();
+        }
+
+        quote! {
+          #[allow(dead_code)]
+          impl$impl_generics $entity_type $where_clause {
+            $(for l in lookups join ($['\n']) => $l)
+          }
+        }
+    }
+
+    fn get_variants(&self) -> Vec<&VariantReceiver> {
+        self.data
+            .as_ref()
+            .take_enum()
+            .expect("Structs are not enums")
+    }
+
+    /// Unknown discriminants, e.g. written by a newer version of the enum,
+    /// are read as the variant marked `#[lean_buffer(fallback)]`, otherwise the first variant
+    fn get_fallback_variant(&self) -> String {
+        let variants = self.get_variants();
+        variants
             .iter()
-            .enumerate()
-            .map(|(i, p)| (p.to_sorting_priority(), p.encode_flatten(i * 2 + 4)))
-            .collect();
+            .find(|v| v.fallback)
+            .or(variants.first())
+            .expect("every enum has variants, see `check_variants`")
+            .ident
+            .to_string()
+    }
 
-        props_unsorted.sort_by(|a, b| a.0.cmp(&b.0));
-        let props: Vec<Tokens<Rust>> = props_unsorted.iter().map(|t| t.1.clone()).collect();
+    fn get_discriminants(&self) -> Vec<(String, i128)> {
+        self.get_variants()
+            .iter()
+            .map(|v| (v.ident.to_string(), v.value))
+            .collect()
+    }
+
+    fn generate_enum_factory(&self, factory: &rust::Import) -> Tokens<Rust> {
+        let fb_table = &rust::import("flatbuffers", "Table");
+        let factory_ext = &rust::import("lean_buffer::traits", "FactoryExt");
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let error = &rust::import("lean_buffer::traits", "Error");
+        let discriminant_type = smallest_discriminant_type(&self.get_discriminants());
 
         quote! {
-          impl $bridge_trait for $entity {
-            fn flatten(&self, builder: &mut $flatbuffer_builder<'_>) {
-              builder.reset();
-              $unnested_props
-              let wip_offset_unfinished = builder.start_table();
-              $props
-              let wip_offset_finished = builder.end_table(wip_offset_unfinished);
-              builder.finish_minimal(wip_offset_finished);
+          impl$impl_generics $factory_ext<$entity_type> for $factory<$entity_type> $where_clause {
+            fn try_inflate<'buf>(&self, table: &mut $fb_table<'buf>) -> Result<$entity_type, $error> {
+              unsafe {
+                match table.get::<$discriminant_type>(4, None) {
+                  Some(d) => <$entity_type as $lean_buffer>::try_inflate_slot(d),
+                  None => Ok(self.new_object()),
+                }
+              }
+            }
+
+            fn new_object(&self) -> $entity_type {
+              <$entity_type as $lean_buffer>::new_slot()
             }
           }
         }
     }
-}
 
-#[derive(Debug, FromField)]
-#[darling(attributes(lean_buffer_internal))]
-pub struct FieldReceiver {
-    ident: Option<syn::Ident>,
-    ty: syn::Type,
-}
+    /// The root of a buffer must be a table, so the discriminant is wrapped in one
+    fn generate_enum_table_adapter(&self) -> Tokens<Rust> {
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let bridge_trait = &rust::import("lean_buffer::traits", "AdapterExt");
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let flatbuffer_builder = &rust::import("flatbuffers", "FlatBufferBuilder");
+        let wip_offset = &rust::import("flatbuffers", "WIPOffset");
+        let table_finished = &rust::import("flatbuffers", "TableFinishedWIPOffset");
 
-impl FieldReceiver {
-    fn get_name(&self) -> String {
-        match &self.ident {
-            Some(i) => i.to_string(),
-            None => String::new(),
+        quote! {
+          impl$impl_generics $bridge_trait for $entity_type $where_clause {
+            fn flatten_table(&self, builder: &mut $flatbuffer_builder<'_>) -> $wip_offset<$table_finished> {
+              let discriminant = $lean_buffer::flatten_slot(self, builder);
+              let wip_offset_unfinished = builder.start_table();
+              builder.push_slot_always(4, discriminant);
+              builder.end_table(wip_offset_unfinished)
+            }
+            $(self.generate_file_identifier_fn())
+          }
         }
     }
 
-    fn as_struct_property_default(&self) -> Tokens<Rust> {
-        let name = self.ident.clone().unwrap().to_string();
-        let ty = path_visitor::get_idents_from_path(&self.ty);
-        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+    /// Fieldless enums are stored inline as their discriminant
+    fn generate_enum_lean_buffer(&self) -> Tokens<Rust> {
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let flatbuffer_builder = &rust::import("flatbuffers", "FlatBufferBuilder");
 
-        if joined.starts_with("Option") {
-            let r = quote! {
-                $name: None
-            };
-            match joined.as_str() {
-                "OptionString" => r,
-                "Optionchar" => r,
-                "Optionbool" => r,
-                "Optionf32" => r,
-                "Optionf64" => r,
-                "Optioni8" => r,
-                "Optionu8" => r,
-                "Optioni16" => r,
-                "Optionu16" => r,
-                "Optioni32" => r,
-                "Optionu32" => r,
-                "Optioni64" => r,
-                "Optionu64" => r,
-                _ => panic!("Not supported: {}", joined),
+        let error = &rust::import("lean_buffer::traits", "Error");
+
+        let verifier = &rust::import("flatbuffers", "Verifier");
+        let invalid = &rust::import("flatbuffers", "InvalidFlatbuffer");
+
+        let discriminants = self.get_discriminants();
+        let discriminant_type = smallest_discriminant_type(&discriminants);
+        let default_variant = discriminants[0].0.clone();
+        let fallback_variant = self.get_fallback_variant();
+
+        quote! {
+          impl$impl_generics $lean_buffer for $entity_type $where_clause {
+            type Slot = $discriminant_type;
+            type Followed<'buf> = $discriminant_type;
+            type Verified = $discriminant_type;
+            $(self.generate_file_identifier_const())
+
+            fn flatten_slot(&self, _builder: &mut $flatbuffer_builder<'_>) -> Self::Slot {
+              match self {
+                $(for (v, d) in &discriminants join () => Self::$v => $(*d),)
+              }
             }
-        } else if joined.starts_with("Vec") {
-            let prim = joined.replace("Vec", "");
-            let r = quote! {
-                $name: Vec::<$prim>::new()
-            };
-            match joined.as_str() {
-                "VecString" => r,
-                "Vecchar" => r,
-                "Vecbool" => r,
-                "Vecf32" => r,
-                "Vecf64" => r,
-                "Veci8" => r,
-                "Vecu8" => r,
-                "Veci16" => r,
-                "Vecu16" => r,
-                "Veci32" => r,
-                "Vecu32" => r,
-                "Veci64" => r,
-                "Vecu64" => r,
-                _ => panic!("Not supported: {}", joined),
+
+            fn try_inflate_slot<'buf>(followed: $discriminant_type) -> Result<Self, $error> {
+              Ok(match followed {
+                $(for (v, d) in &discriminants join () => $(*d) => Self::$v,)
+                _ => Self::$fallback_variant,
+              })
             }
-        } else {
-            let r = quote! {
-                $(name.clone()): 0
-            };
-            match joined.as_str() {
-                "String" => quote! {
-                    $name: String::from("")
-                },
-                "char" => quote! {
-                    $name: char::from(0)
-                },
-                "bool" => quote! {
-                    $name: false
-                },
-                "f32" => quote! {
-                    $name: 0.0
-                },
-                "f64" => quote! {
-                    $name: 0.0
-                },
-                "i8" => r,
-                "u8" => r,
-                "i16" => r,
-                "u16" => r,
-                "i32" => r,
-                "u32" => r,
-                "i64" => r,
-                "u64" => r,
-                _ => panic!("Not supported: {}", joined),
+
+            fn new_slot() -> Self {
+              Self::$default_variant
             }
+
+            /// The table that wraps the discriminant of a root
+            fn verify_table(v: &mut $verifier<'_, '_>, pos: usize, _variant: u8) -> Result<(), $invalid> {
+              v.visit_table(pos)?
+                .visit_field::<$discriminant_type>("discriminant", 4, false)?
+                .finish();
+              Ok(())
+            }
+          }
         }
     }
+    /// Enums with at least one variant that carries fields
+    fn is_union(&self) -> bool {
+        self.data.is_enum() && self.get_variants().iter().any(|v| !v.fields.is_unit())
+    }
 
-    fn as_assigned_property(&self, offset: usize) -> Tokens<Rust> {
+    /// The union type of each variant, 0 is reserved for `NONE` by flatbuffers
+    fn get_union_types(&self) -> Vec<(&VariantReceiver, u8)> {
+        self.get_variants()
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (v, i as u8 + 1))
+            .collect()
+    }
+
+    fn generate_union_factory(&self, factory: &rust::Import) -> Tokens<Rust> {
+        let fb_table = &rust::import("flatbuffers", "Table");
+        let factory_ext = &rust::import("lean_buffer::traits", "FactoryExt");
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let error = &rust::import("lean_buffer::traits", "Error");
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+
+        quote! {
+          impl$impl_generics $factory_ext<$entity_type> for $factory<$entity_type> $where_clause {
+            fn try_inflate<'buf>(&self, table: &mut $fb_table<'buf>) -> Result<$entity_type, $error> {
+              <$entity_type as $lean_buffer>::try_inflate_slot(*table)
+            }
+
+            fn new_object(&self) -> $entity_type {
+              <$entity_type as $lean_buffer>::new_slot()
+            }
+          }
+        }
+    }
+
+    /// A table with the union type in the first slot,
+    /// and the table of the variant's fields in the second slot
+    fn generate_union_lean_buffer(&self) -> Tokens<Rust> {
+        let entity = &self.get_entity();
+        let (impl_generics, entity_type, where_clause) = &self.get_generics(entity);
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let flatbuffer_builder = &rust::import("flatbuffers", "FlatBufferBuilder");
+        let fb_table = &rust::import("flatbuffers", "Table");
         let fuo = &rust::import("flatbuffers", "ForwardsUOffset");
-        let fvec = &rust::import("flatbuffers", "Vector");
+        let wip_offset = &rust::import("flatbuffers", "WIPOffset");
+        let table_finished = &rust::import("flatbuffers", "TableFinishedWIPOffset");
+        let error = &rust::import("lean_buffer::traits", "Error");
+        let verifier = &rust::import("flatbuffers", "Verifier");
+        let invalid = &rust::import("flatbuffers", "InvalidFlatbuffer");
+        let verified_table = &rust::import("lean_buffer::traits", "VerifiedTable");
 
-        let name = &self.ident.clone().unwrap().to_string();
-        let ty = path_visitor::get_idents_from_path(&self.ty);
-        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+        let union_types = self.get_union_types();
+        let fallback_variant = self.get_fallback_variant();
 
-        if joined.starts_with("Option") {
-            let prim = joined.replace("Option", "");
-            let r = quote! {
-                *$name = table.get::<$prim>($offset, None);
-            };
-            match joined.as_str() {
-                "OptionString" => quote! {
-                    *$name = table.get::<$fuo<&str>>($offset, None).map(|s|s.to_string());
-                },
-                "Optionchar" => quote! {
+        let flattened_variants: Vec<Tokens<Rust>> = union_types
+            .iter()
+            .map(|(v, t)| {
+                let fields = v.fields.as_ref();
+                let destructured = destructure(&v.get_path(), &fields);
+                if fields.is_unit() {
+                    // flatbuffers expects a value for every union type but `NONE`
+                    quote! {
+                        $destructured => {
+                            let wip_offset_unfinished = builder.start_table();
+                            ($(*t), Some(builder.end_table(wip_offset_unfinished)))
+                        }
+                    }
+                } else {
+                    let table = generate_table(&fields.fields);
+                    quote! {
+                        $destructured => {
+                            let union_value = { $table };
+                            ($(*t), Some(union_value))
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        let inflated_variants: Vec<Tokens<Rust>> = union_types
+            .iter()
+            .map(|(v, t)| {
+                let fields = v.fields.as_ref();
+                let constructed = construct_default(&v.get_path(), &fields);
+                if fields.is_unit() {
+                    quote!(Some($(*t)) => $constructed,)
+                } else {
+                    let destructured = destructure(&v.get_path(), &fields);
+                    let assigned_props = generate_assigned_properties(&fields.fields);
+                    quote! {
+                        Some($(*t)) => {
+                            let mut object = $constructed;
+                            if let ($destructured, Some(table)) =
+                                (&mut object, followed.get::<$fuo<$fb_table<'buf>>>(6, None))
+                            {
+                                $assigned_props
+                            }
+                            object
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        let verified_variants: Vec<Tokens<Rust>> = union_types
+            .iter()
+            .map(|(v, t)| {
+                let verified_fields = generate_verified_fields(&v.fields.as_ref().fields);
+                quote! {
+                    $(*t) => {
+                        v.visit_table(pos)?
+                          $verified_fields
+                          .finish();
+                    }
+                }
+            })
+            .collect();
+        let verified_union_types: Vec<Tokens<Rust>> = union_types
+            .iter()
+            .map(|(v, t)| {
+                quote! {
+                    $(*t) => v.verify_union_variant::<$fuo<$verified_table<Self, $(*t)>>>($(quoted(v.ident.to_string())), pos),
+                }
+            })
+            .collect();
+
+        let fallback = union_types
+            .iter()
+            .find(|(v, _)| v.ident == fallback_variant)
+            .map(|(v, _)| construct_default(&v.get_path(), &v.fields.as_ref()))
+            .expect("Missing fallback variant");
+        let default = union_types
+            .first()
+            .map(|(v, _)| construct_default(&v.get_path(), &v.fields.as_ref()))
+            .expect("every enum has variants, see `check_variants`");
+
+        quote! {
+          impl$impl_generics $lean_buffer for $entity_type $where_clause {
+            type Slot = $wip_offset<$table_finished>;
+            type Followed<'buf> = $fuo<$fb_table<'buf>>;
+            type Verified = $fuo<$verified_table<Self>>;
+            $(self.generate_file_identifier_const())
+
+            fn flatten_slot(&self, builder: &mut $flatbuffer_builder<'_>) -> Self::Slot {
+              let (union_type, union_value): (u8, Option<Self::Slot>) = match self {
+                $flattened_variants
+              };
+              let wip_offset_unfinished = builder.start_table();
+              if let Some(v) = union_value {
+                builder.push_slot_always(6, v);
+              }
+              builder.push_slot_always(4, union_type);
+              builder.end_table(wip_offset_unfinished)
+            }
+
+            fn try_inflate_slot<'buf>(followed: $fb_table<'buf>) -> Result<Self, $error> {
+              unsafe {
+                Ok(match followed.get::<u8>(4, None) {
+                  $inflated_variants
+                  _ => $fallback,
+                })
+              }
+            }
+
+            fn new_slot() -> Self {
+              $default
+            }
+
+            /// The table of the union, then the table of its variant,
+            /// an unknown union type is skipped, as it is inflated to the fallback
+            fn verify_table(v: &mut $verifier<'_, '_>, pos: usize, variant: u8) -> Result<(), $invalid> {
+              match variant {
+                0 => {
+                  v.visit_table(pos)?
+                    .visit_union::<u8, _>("union_type", 4, "union_value", 6, false, |union_type, v, pos| match union_type {
+                      $verified_union_types
+                      _ => Ok(()),
+                    })?
+                    .finish();
+                }
+                $verified_variants
+                _ => {}
+              }
+              Ok(())
+            }
+          }
+        }
+    }
+}
+
+/// Every enum has variants, at most one of them is the fallback, and a union has at most 255;
+/// the discriminants of a fieldless enum are evaluated,
+/// implicit ones continue from the previous one, like rustc does
+fn check_variants(
+    ident: &syn::Ident,
+    variants: &mut [VariantReceiver],
+    is_union: bool,
+) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    if variants.is_empty() {
+        errors.push(darling::Error::custom("Not supported: an enum without variants").with_span(ident));
+    }
+    if is_union && variants.len() > u8::MAX as usize {
+        errors.push(
+            darling::Error::custom(format!("Not supported: more than {} variants with fields", u8::MAX))
+                .with_span(ident),
+        );
+    }
+    for v in variants.iter().filter(|v| v.fallback).skip(1) {
+        errors.push(darling::Error::custom("Only one variant can be the fallback").with_span(&v.ident));
+    }
+    if !is_union {
+        let mut next = 0;
+        for v in variants.iter_mut() {
+            if let Some(expr) = &v.discriminant {
+                match errors.handle(eval_discriminant(expr)) {
+                    Some(value) => next = value,
+                    None => continue,
+                }
+            }
+            v.value = next;
+            next += 1;
+        }
+    }
+    errors.finish()
+}
+
+/// Only integer literals, optionally negated, are supported as explicit discriminants,
+/// since the smallest type that fits them is chosen before rustc evaluates anything
+fn eval_discriminant(expr: &syn::Expr) -> darling::Result<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse::<i128>().map_err(darling::Error::from),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => eval_discriminant(expr).map(|value| -value),
+        syn::Expr::Group(g) => eval_discriminant(&g.expr),
+        syn::Expr::Paren(p) => eval_discriminant(&p.expr),
+        _ => Err(darling::Error::custom(format!(
+            "Not supported: the discriminant `{}`, only an integer literal",
+            expr.to_token_stream()
+        ))
+        .with_span(expr)),
+    }
+}
+
+/// The smallest integer type that fits every discriminant
+fn smallest_discriminant_type(discriminants: &[(String, i128)]) -> &'static str {
+    let min = discriminants.iter().map(|d| d.1).min().unwrap_or(0);
+    let max = discriminants.iter().map(|d| d.1).max().unwrap_or(0);
+
+    if min >= 0 {
+        match max {
+            m if m <= u8::MAX as i128 => "u8",
+            m if m <= u16::MAX as i128 => "u16",
+            m if m <= u32::MAX as i128 => "u32",
+            _ => "u64",
+        }
+    } else if min >= i8::MIN as i128 && max <= i8::MAX as i128 {
+        "i8"
+    } else if min >= i16::MIN as i128 && max <= i16::MAX as i128 {
+        "i16"
+    } else if min >= i32::MIN as i128 && max <= i32::MAX as i128 {
+        "i32"
+    } else {
+        "i64"
+    }
+}
+
+#[derive(Debug, FromVariant)]
+#[darling(attributes(lean_buffer), and_then = Self::number_fields)]
+pub struct VariantReceiver {
+    ident: syn::Ident,
+    discriminant: Option<syn::Expr>,
+    fields: ast::Fields<FieldReceiver>,
+    #[darling(default)]
+    fallback: bool,
+    /// As on a struct, since every variant has its own table
+    #[darling(default, multiple)]
+    deprecated: Vec<u16>,
+    /// Of a fieldless enum, evaluated from the discriminant, see `check_variants`
+    #[darling(skip)]
+    value: i128,
+}
+
+impl VariantReceiver {
+    fn number_fields(mut self) -> darling::Result<Self> {
+        number_fields(&mut self.fields);
+        assign_offsets(&mut self.fields, &self.deprecated)?;
+        Ok(self)
+    }
+
+    fn get_path(&self) -> Tokens<Rust> {
+        quote!(Self::$(self.ident.to_string()))
+    }
+}
+
+fn number_fields(fields: &mut ast::Fields<FieldReceiver>) {
+    for (i, f) in fields.fields.iter_mut().enumerate() {
+        f.index = i;
+    }
+}
+
+/// Names and aliases are looked up together, so none may repeat
+fn check_names(fields: &ast::Fields<FieldReceiver>) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    let mut taken: Vec<String> = Vec::new();
+    for f in fields.iter().filter(|f| !f.skip) {
+        for name in std::iter::once(f.get_wire_name()).chain(f.alias.iter().cloned()) {
+            if taken.contains(&name) {
+                errors.push(f.error(&format!("Duplicate name or alias `{}`", name)));
+            }
+            taken.push(name);
+        }
+    }
+    errors.finish()
+}
+
+/// 4 alphanumeric characters from the FNV-1a hash of `name`,
+/// so types with a common prefix, e.g. `Entity` and `EntityVecs`, still differ
+fn derive_file_identifier(name: &str) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let mut hash = name.bytes().fold(0x811c9dc5u32, |h, b| (h ^ b as u32).wrapping_mul(0x01000193));
+    (0..4)
+        .map(|_| {
+            let c = ALPHABET[(hash % ALPHABET.len() as u32) as usize] as char;
+            hash /= ALPHABET.len() as u32;
+            c
+        })
+        .collect()
+}
+
+/// A scalar is left out when it equals its default, so it cannot be told apart from an absent one
+fn check_required(fields: &ast::Fields<FieldReceiver>) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    for f in fields.iter().filter(|f| f.required) {
+        if f.skip || f.get_outer_type_name() == "Option" {
+            errors.push(f.error("Not supported: `required` on a skipped or optional field"));
+        } else if f.get_array_type().is_none() && f.get_unnested_binding(f.offset).is_none() {
+            errors.push(f.error("Not supported: `required` on a scalar, use a `default` instead"));
+        }
+    }
+    errors.finish()
+}
+
+/// A `default` is left out on write and restored on read, which only a plain scalar supports,
+/// a skipped field takes any `default`, since it is never written
+fn check_defaults(fields: &ast::Fields<FieldReceiver>) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    for f in fields.iter().filter(|f| f.default.is_some() && !f.skip) {
+        if !f.is_plain_scalar() {
+            errors.push(f.error("Not supported: `default` on anything but a plain scalar, e.g. a `u32` or `bool`"));
+        }
+    }
+    errors.finish()
+}
+
+/// Every field that is not skipped gets the vtable offset of its id,
+/// either all ids are explicit, or they follow the declaration order around the deprecated ones;
+/// explicit ids must not repeat, and together with the deprecated ones leave no gaps
+fn assign_offsets(fields: &mut ast::Fields<FieldReceiver>, deprecated: &[u16]) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    let mut slotted: Vec<&mut FieldReceiver> = fields.fields.iter_mut().filter(|f| !f.skip).collect();
+
+    if slotted.iter().all(|f| f.id.is_none()) {
+        let mut ids = (0u16..).filter(|id| !deprecated.contains(id));
+        for f in slotted.iter_mut() {
+            f.offset = ids.next().map(id_to_offset).unwrap_or_default();
+        }
+        return errors.finish();
+    }
+
+    let mut taken: Vec<u16> = Vec::new();
+    for id in deprecated {
+        if taken.contains(id) {
+            errors.push(darling::Error::custom(format!("Duplicate deprecated id {}", id)));
+        }
+        taken.push(*id);
+    }
+    for f in slotted.iter_mut() {
+        match f.id {
+            None => errors.push(f.error("Missing `id`, either every field has one, or none")),
+            Some(id) if taken.contains(&id) => {
+                errors.push(f.error(&format!("Duplicate id {}", id)))
+            }
+            Some(id) => {
+                taken.push(id);
+                f.offset = id_to_offset(id);
+            }
+        }
+    }
+    taken.sort_unstable();
+    if let Some(gap) = (0u16..).zip(taken.iter()).find(|(i, id)| i != *id).map(|(i, _)| i) {
+        errors.push(darling::Error::custom(format!(
+            "Missing id {}, reserve it with `#[lean_buffer(deprecated = {})]`",
+            gap, gap
+        )));
+    }
+    errors.finish()
+}
+
+fn id_to_offset(id: u16) -> usize {
+    id as usize * 2 + 4
+}
+
+/// e.g. `Entity { a: __lb_a, b: __lb_b }`, `Entity(__lb_f0, __lb_f1)` or `Entity`,
+/// skipped fields are ignored
+fn destructure(path: &Tokens<Rust>, fields: &ast::Fields<&FieldReceiver>) -> Tokens<Rust> {
+    match fields.style {
+        ast::Style::Struct => quote! {
+            $path { $(for f in fields.iter() join (, ) => $(f.get_name()): $(if f.skip { _ } else { $(f.get_binding()) })) }
+        },
+        ast::Style::Tuple => quote! {
+            $path($(for f in fields.iter() join (, ) => $(if f.skip { _ } else { $(f.get_binding()) })))
+        },
+        ast::Style::Unit => quote!($path),
+    }
+}
+
+/// e.g. `Entity { a: 0, b: String::from("") }`, `Entity(0, String::from(""))` or `Entity`
+fn construct_default(path: &Tokens<Rust>, fields: &ast::Fields<&FieldReceiver>) -> Tokens<Rust> {
+    match fields.style {
+        ast::Style::Struct => quote! {
+            $path { $(for f in fields.iter() join (, ) => $(f.as_struct_property_default())) }
+        },
+        ast::Style::Tuple => quote! {
+            $path($(for f in fields.iter() join (, ) => $(f.as_struct_property_default())))
+        },
+        ast::Style::Unit => quote!($path),
+    }
+}
+
+/// e.g. `Entity { a: 42, ..Default::default() }`, or just `Default::default()`,
+/// only fields with a `default` or `default_fn` of their own are assigned
+fn construct_from_default(path: &Tokens<Rust>, fields: &ast::Fields<&FieldReceiver>) -> Tokens<Rust> {
+    let assigned: Vec<&&FieldReceiver> = fields
+        .iter()
+        .filter(|f| f.default.is_some() || (f.skip && f.default_fn.is_some()))
+        .collect();
+    if assigned.is_empty() {
+        return quote!(Default::default());
+    }
+    quote! {
+        $path { $(for f in assigned join (, ) => $(f.get_member()): $(f.as_default_value())), ..Default::default() }
+    }
+}
+
+/// Visits the slot of each field, chained on a `TableVerifier`
+fn generate_verified_fields(fields: &[&FieldReceiver]) -> Tokens<Rust> {
+    let visited = slotted(fields).into_iter().map(|(offset, f)| {
+        quote! {
+            .visit_field::<$(f.as_verified_type())>($(quoted(f.get_wire_name())), $offset, $(f.required.to_string()))?
+        }
+    });
+
+    quote! {
+        $(for v in visited join () => $v)
+    }
+}
+
+/// The vtable offset of each field that is not skipped
+fn slotted<'f>(fields: &[&'f FieldReceiver]) -> Vec<(usize, &'f FieldReceiver)> {
+    fields
+        .iter()
+        .filter(|f| !f.skip)
+        .map(|f| (f.offset, *f))
+        .collect()
+}
+
+/// Writes the destructured fields into a new table, preceded by anything nested,
+/// evaluates to the offset of the finished table
+fn generate_table(fields: &[&FieldReceiver]) -> Tokens<Rust> {
+    let unnested_props: Vec<Tokens<Rust>> = slotted(fields)
+        .iter()
+        .map(|(offset, p)| p.encode_flatten_unnested(*offset))
+        .collect();
+
+    let mut props_unsorted: Vec<(usize, Tokens<Rust>)> = slotted(fields)
+        .iter()
+        .map(|(offset, p)| (p.to_sorting_priority(), p.encode_flatten(*offset)))
+        .collect();
+
+    props_unsorted.sort_by(|a, b| a.0.cmp(&b.0));
+    let props: Vec<Tokens<Rust>> = props_unsorted.iter().map(|t| t.1.clone()).collect();
+
+    quote! {
+        $unnested_props
+        let wip_offset_unfinished = builder.start_table();
+        $props
+        builder.end_table(wip_offset_unfinished)
+    }
+}
+
+/// Assigns the destructured fields from `table`
+fn generate_assigned_properties(fields: &[&FieldReceiver]) -> Tokens<Rust> {
+    let error = &rust::import("lean_buffer::traits", "Error");
+    let assigned_props = slotted(fields).into_iter().map(|(offset, p)| {
+        let assigned = p.as_assigned_property(offset);
+        match p.required {
+            true => quote! {
+                if table.vtable().get($offset) == 0 {
+                    return Err($error::MissingField { field: $(quoted(p.get_unraw_name())) });
+                }
+                $assigned
+            },
+            false => assigned,
+        }
+    });
+
+    quote! {
+        $(for p in assigned_props join () => $(p))
+    }
+}
+
+#[derive(Debug, Clone, FromField)]
+#[darling(attributes(lean_buffer))]
+pub struct FieldReceiver {
+    ident: Option<syn::Ident>,
+    ty: syn::Type,
+    /// Position in the struct or variant, names the binding of a positional field
+    #[darling(skip)]
+    index: usize,
+    /// Not written and without a slot, e.g. a cache or a channel handle,
+    /// inflated from `Default::default()` or `default_fn`
+    #[darling(default)]
+    skip: bool,
+    #[darling(default)]
+    default_fn: Option<syn::Path>,
+    /// Initial value, e.g. `#[lean_buffer(default = 42)]`,
+    /// a scalar that equals it is left out, and restored from it
+    #[darling(default)]
+    default: Option<syn::Expr>,
+    /// Of a scalar, falls back to the field of the struct's `Default` impl
+    #[darling(skip)]
+    struct_default: bool,
+    /// Exported instead of the field name, e.g. `#[lean_buffer(rename = "userName")]`
+    #[darling(default)]
+    rename: Option<String>,
+    /// Earlier names, still recognized, e.g. `#[lean_buffer(alias = "user_name")]`
+    #[darling(default, multiple)]
+    alias: Vec<String>,
+    /// Inflate fails when the slot is absent, instead of leaving the field empty,
+    /// only for fields written to an offset, e.g. a `String`, `Vec` or nested struct
+    #[darling(default)]
+    required: bool,
+    /// Pins the slot, so fields can be reordered or inserted, e.g. `#[lean_buffer(id = 3)]`
+    #[darling(default)]
+    id: Option<u16>,
+    /// Of the slot, derived from the id
+    #[darling(skip)]
+    offset: usize,
+}
+
+impl FieldReceiver {
+    fn error(&self, message: &str) -> darling::Error {
+        let e = darling::Error::custom(message);
+        match &self.ident {
+            Some(i) => e.with_span(i),
+            None => e.with_span(&self.ty),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        match &self.ident {
+            Some(i) => i.to_string(),
+            None => format!("f{}", self.index),
+        }
+    }
+
+    /// The name without a raw prefix, to build identifiers from, e.g. `loop` of `r#loop`
+    fn get_unraw_name(&self) -> String {
+        match &self.ident {
+            Some(i) => i.unraw().to_string(),
+            None => format!("f{}", self.index),
+        }
+    }
+
+    /// The local that a field is destructured into, e.g. `__lb_builder`,
+    /// prefixed so it cannot shadow `builder`, `table` or any other local of the generated code
+    fn get_binding(&self) -> String {
+        match &self.ident {
+            Some(i) => format!("__lb_{}", i.unraw()),
+            None => format!("__lb_f{}", self.index),
+        }
+    }
+
+    /// e.g. `name` or `0`, as in `object.name` or `object.0`
+    fn get_member(&self) -> String {
+        match &self.ident {
+            Some(i) => i.to_string(),
+            None => self.index.to_string(),
+        }
+    }
+
+    /// e.g. `userName` of `#[lean_buffer(rename = "userName")]`, or just the member without `r#`
+    fn get_wire_name(&self) -> String {
+        match (&self.rename, &self.ident) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(i)) => i.unraw().to_string(),
+            (None, None) => self.index.to_string(),
+        }
+    }
+
+    /// A number, `bool` or `char`, written to its slot directly
+    fn is_plain_scalar(&self) -> bool {
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+        matches!(
+            joined.as_str(),
+            "bool" | "char" | "f32" | "f64" | "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64"
+                | "u64" | "isize" | "usize"
+        )
+    }
+
+    /// What a scalar is compared with before it is left out,
+    /// e.g. `42` of `#[lean_buffer(default = 42)]`, or `default_object.len as u64`
+    fn get_elided_default(&self) -> Option<String> {
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+        let wide = match joined.as_str() {
+            "char" => "u32",
+            "isize" => "i64",
+            "usize" => "u64",
+            "bool" | "f32" | "f64" | "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64"
+            | "u64" => "",
+            _ => return None,
+        };
+        let value = match (&self.default, self.struct_default) {
+            (Some(e), _) => e.to_token_stream().to_string(),
+            (None, true) => format!("default_object.{}", self.get_member()),
+            (None, false) => return None,
+        };
+        Some(match wide {
+            "" => value,
+            "u32" => format!("u32::from({})", value),
+            _ if self.default.is_some() => value,
+            _ => format!("{} as {}", value, wide),
+        })
+    }
+
+    /// Anything not matched is presumed to be another derived type
+    fn get_type_name(&self) -> String {
+        self.ty.to_token_stream().to_string()
+    }
+
+    /// The element type of e.g. `Vec<T>`, lifetimes are skipped as in `Cow<'static, str>`
+    fn get_inner_type(&self) -> &syn::Type {
+        if let syn::Type::Path(p) = &self.ty {
+            if let Some(syn::PathArguments::AngleBracketed(args)) =
+                p.path.segments.last().map(|s| &s.arguments)
+            {
+                if let Some(t) = args.args.iter().find_map(|a| match a {
+                    syn::GenericArgument::Type(t) => Some(t),
+                    _ => None,
+                }) {
+                    return t;
+                }
+            }
+        }
+        panic!("Not supported: {}", self.get_type_name())
+    }
+
+    fn get_inner_type_name(&self) -> String {
+        self.get_inner_type().to_token_stream().to_string()
+    }
+
+    /// The final path segments, joined, e.g. `VecString` of `Vec<std::string::String>`
+    fn get_joined_type_name(&self) -> String {
+        path_visitor::get_idents_from_path(&self.ty)
+            .iter()
+            .map(|i| i.to_string())
+            .collect()
+    }
+
+    /// The final path segment, e.g. `Vec` of `std::vec::Vec<u8>`
+    fn get_outer_type_name(&self) -> String {
+        path_visitor::get_idents_from_path(&self.ty)
+            .first()
+            .map(|i| i.to_string())
+            .unwrap_or_default()
+    }
+
+    /// The same field, typed as `T` of an `Option<T>` that is written to an offset
+    /// or inline array, e.g. `Option<Vec<u32>>`, `Option<String>` or `Option<Nested>`;
+    /// it is absent when its slot is absent, and present when empty
+    fn get_optional_inner(&self) -> Option<FieldReceiver> {
+        if self.get_outer_type_name() != "Option" {
+            return None;
+        }
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+        match joined.as_str() {
+            "Optionchar" | "Optionbool" | "Optionf32" | "Optionf64" | "Optioni8" | "Optionu8"
+            | "Optioni16" | "Optionu16" | "Optioni32" | "Optionu32" | "Optioni64"
+            | "Optionu64" | "Optionisize" | "Optionusize" => None,
+            _ => Some(FieldReceiver {
+                ty: self.get_inner_type().clone(),
+                default: None,
+                struct_default: false,
+                ..self.clone()
+            }),
+        }
+    }
+
+    /// The same field, typed as what `Box`, `Rc`, `Arc` or `Cow` wraps,
+    /// with `str` as `String` and `[T]` as `Vec<T>`;
+    /// the wrapper is transparent, and rebuilt with `From` on inflate
+    fn get_wrapped_inner(&self) -> Option<FieldReceiver> {
+        match self.get_outer_type_name().as_str() {
+            "Box" | "Rc" | "Arc" | "Cow" => {}
+            _ => return None,
+        }
+        let ty: syn::Type = match self.get_inner_type() {
+            syn::Type::Path(p) if p.path.is_ident("str") => syn::parse_quote!(String),
+            syn::Type::Slice(s) => {
+                let elem = &s.elem;
+                syn::parse_quote!(Vec<#elem>)
+            }
+            t => t.clone(),
+        };
+        Some(FieldReceiver {
+            ty,
+            struct_default: false,
+            ..self.clone()
+        })
+    }
+
+    /// Rebinds a wrapped field to a reference of what it wraps, e.g. `&str` of `&Arc<str>`
+    fn unwrap_binding(&self) -> Tokens<Rust> {
+        quote!(let $(self.get_binding()): &$(self.get_inner_type_name()) = $(self.get_binding());)
+    }
+
+    /// The `key` and `value` fields of e.g. `HashMap<K, V>` or `BTreeMap<K, V>`,
+    /// each entry is written as a table, sorted by key
+    fn get_map_entry(&self) -> Option<(FieldReceiver, FieldReceiver)> {
+        match self.get_outer_type_name().as_str() {
+            "HashMap" | "BTreeMap" => {}
+            _ => return None,
+        }
+        if let syn::Type::Path(p) = &self.ty {
+            if let Some(syn::PathArguments::AngleBracketed(args)) =
+                p.path.segments.last().map(|s| &s.arguments)
+            {
+                let types: Vec<&syn::Type> = args
+                    .args
+                    .iter()
+                    .filter_map(|a| match a {
+                        syn::GenericArgument::Type(t) => Some(t),
+                        _ => None,
+                    })
+                    .collect();
+                if let [key, value, ..] = types[..] {
+                    // none of the attributes of the map field apply to its entries,
+                    // e.g. a `required` map still elides a zero key
+                    let entry = |name: &str, ty: &syn::Type, id: u16| FieldReceiver {
+                        ident: Some(quote::format_ident!("{}", name)),
+                        ty: ty.clone(),
+                        index: id as usize,
+                        skip: false,
+                        default_fn: None,
+                        default: None,
+                        struct_default: false,
+                        rename: None,
+                        alias: vec![],
+                        required: false,
+                        id: Some(id),
+                        offset: id_to_offset(id),
+                    };
+                    let (key, value) = (entry("key", key, 0), entry("value", value, 1));
+                    // ordered keys only
+                    return match key.get_joined_type_name().as_str() {
+                        "String" | "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" => {
+                            Some((key, value))
+                        }
+                        _ => panic!("Not supported: {}", self.get_type_name()),
+                    };
+                }
+            }
+        }
+        panic!("Not supported: {}", self.get_type_name())
+    }
+
+    /// The binding that `encode_flatten_unnested` prepares for `encode_flatten`, if any
+    fn get_unnested_binding(&self, offset: usize) -> Option<String> {
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+
+        if self.get_array_type().is_some() {
+            None
+        } else if let Some(inner) = self.get_optional_inner() {
+            inner.get_unnested_binding(offset)
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            inner.get_unnested_binding(offset)
+        } else if self.get_outer_type_name() == "Option" {
+            None
+        } else if self.get_outer_type_name() == "Vec" || self.get_map_entry().is_some() {
+            Some(format!("vec_{}", offset))
+        } else {
+            match joined.as_str() {
+                "String" => Some(format!("str_{}", offset)),
+                "char" | "bool" | "f32" | "f64" | "i8" | "u8" | "i16" | "u16" | "i32" | "u32"
+                | "i64" | "u64" | "isize" | "usize" | "i128" | "u128" => None,
+                _ => Some(format!("nested_{}", offset)),
+            }
+        }
+    }
+
+    /// The element type and length of e.g. `[f32; 3]`, only scalars are stored inline
+    fn get_array_type(&self) -> Option<(String, String)> {
+        if let syn::Type::Array(a) = &self.ty {
+            // e.g. `u8` of `core::primitive::u8`
+            let elem = path_visitor::get_idents_from_path(&a.elem)
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<String>();
+            let len = a.len.to_token_stream().to_string();
+            match elem.as_str() {
+                "bool" | "f32" | "f64" | "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64"
+                | "u64" => Some((elem, len)),
+                _ => panic!("Not supported: {}", self.get_type_name()),
+            }
+        } else {
+            None
+        }
+    }
+
+    /// e.g. `t_u64: 0` for named fields, or just `0` for positional fields
+    fn as_struct_property_default(&self) -> Tokens<Rust> {
+        let value = self.as_default_value();
+        match &self.ident {
+            Some(name) => quote!($(name.to_string()): $value),
+            None => value,
+        }
+    }
+
+    fn as_default_value(&self) -> Tokens<Rust> {
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+
+        if self.skip {
+            match (&self.default, &self.default_fn) {
+                (Some(e), _) => quote!($(e.to_token_stream().to_string())),
+                (None, Some(f)) => quote!($(f.to_token_stream().to_string())()),
+                (None, None) => quote!(Default::default()),
+            }
+        } else if let (Some(e), None) = (&self.default, self.get_wrapped_inner()) {
+            quote!($(e.to_token_stream().to_string()))
+        } else if let Some((elem, len)) = self.get_array_type() {
+            match elem.as_str() {
+                "bool" => quote!([false; $len]),
+                "f32" | "f64" => quote!([0.0; $len]),
+                _ => quote!([0; $len]),
+            }
+        } else if self.get_optional_inner().is_some() {
+            quote!(None)
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            quote!(<$(self.get_type_name())>::from($(inner.as_default_value())))
+        } else if self.get_map_entry().is_some() {
+            quote!(<$(self.get_type_name())>::default())
+        } else if self.get_outer_type_name() == "Option" {
+            let r = quote! {
+                None
+            };
+            match joined.as_str() {
+                "Optionchar" => r,
+                "Optionbool" => r,
+                "Optionf32" => r,
+                "Optionf64" => r,
+                "Optioni8" => r,
+                "Optionu8" => r,
+                "Optioni16" => r,
+                "Optionu16" => r,
+                "Optioni32" => r,
+                "Optionu32" => r,
+                "Optioni64" => r,
+                "Optionu64" => r,
+                "Optionisize" => r,
+                "Optionusize" => r,
+                _ => panic!("Not supported: {}", joined),
+            }
+        } else if self.get_outer_type_name() == "Vec" {
+            let prim = joined.replace("Vec", "");
+            let r = quote! {
+                Vec::<$prim>::new()
+            };
+            match joined.as_str() {
+                "VecString" => r,
+                "Vecchar" => r,
+                "Vecbool" => r,
+                "Vecf32" => r,
+                "Vecf64" => r,
+                "Veci8" => r,
+                "Vecu8" => r,
+                "Veci16" => r,
+                "Vecu16" => r,
+                "Veci32" => r,
+                "Vecu32" => r,
+                "Veci64" => r,
+                "Vecu64" => r,
+                _ => quote! {
+                    Vec::<$(self.get_inner_type_name())>::new()
+                },
+            }
+        } else {
+            let r = quote! {
+                0
+            };
+            match joined.as_str() {
+                "String" => quote! {
+                    String::from("")
+                },
+                "char" => quote! {
+                    char::from(0)
+                },
+                "bool" => quote! {
+                    false
+                },
+                "f32" => quote! {
+                    0.0
+                },
+                "f64" => quote! {
+                    0.0
+                },
+                "i8" => r,
+                "u8" => r,
+                "i16" => r,
+                "u16" => r,
+                "i32" => r,
+                "u32" => r,
+                "i64" => r,
+                "u64" => r,
+                "isize" => r,
+                "usize" => r,
+                "i128" => r,
+                "u128" => r,
+                _ => quote! {
+                    <$(self.get_type_name()) as $lean_buffer>::new_slot()
+                },
+            }
+        }
+    }
+
+    fn as_assigned_property(&self, offset: usize) -> Tokens<Rust> {
+        let fuo = &rust::import("flatbuffers", "ForwardsUOffset");
+        let fvec = &rust::import("flatbuffers", "Vector");
+        let farray = &rust::import("flatbuffers", "Array");
+        let error = &rust::import("lean_buffer::traits", "Error");
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+
+        let name = &self.get_binding();
+        let field = &self.get_unraw_name();
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+
+        if let Some((elem, len)) = self.get_array_type() {
+            quote! {
+                if let Some(a) = table.get::<$farray<'buf, $elem, $len>>($offset, None) {
+                    *$name = a.into();
+                }
+            }
+        } else if let Some(inner) = self.get_optional_inner() {
+            let inner_default = inner.as_default_value();
+            let inner_assigned = inner.as_assigned_property(offset);
+            quote! {
+                if table.vtable().get($offset) != 0 {
+                    let mut inner = $inner_default;
+                    {
+                        let $name = &mut inner;
+                        $inner_assigned
+                    }
+                    *$name = Some(inner);
+                }
+            }
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            let inner_default = inner.as_default_value();
+            let inner_assigned = inner.as_assigned_property(offset);
+            quote! {
+                {
+                    let mut inner = $inner_default;
+                    {
+                        let $name = &mut inner;
+                        $inner_assigned
+                    }
+                    *$name = <$(self.get_type_name())>::from(inner);
+                }
+            }
+        } else if let Some((key, value)) = self.get_map_entry() {
+            let fb_table = &rust::import("flatbuffers", "Table");
+            let key_default = key.as_default_value();
+            let value_default = value.as_default_value();
+            let entry_assigned = generate_assigned_properties(&[&key, &value]);
+            quote! {
+                let fb_$field = table.get::<$fuo<$fvec<'buf, $fuo<$fb_table<'buf>>>>>($offset, None);
+                if let Some(v) = fb_$field {
+                    *$name = v.iter().map(|table| -> Result<_, $error> {
+                        let (mut key, mut value) = ($key_default, $value_default);
+                        {
+                            let ($(key.get_binding()), $(value.get_binding())) = (&mut key, &mut value);
+                            $entry_assigned
+                        }
+                        Ok((key, value))
+                    }).collect::<Result<_, _>>()?;
+                }
+            }
+        } else if self.get_outer_type_name() == "Option" {
+            let prim = joined.replace("Option", "");
+            let r = quote! {
+                *$name = table.get::<$prim>($offset, None);
+            };
+            match joined.as_str() {
+                "Optionchar" => quote! {
                     if let Some(v) = table.get::<u32>($offset, None) {
                         *$name = std::char::from_u32(v);
                     }
@@ -353,35 +1728,46 @@ impl FieldReceiver {
                 "Optionu32" => r,
                 "Optioni64" => r,
                 "Optionu64" => r,
+                // with an explicit width, that may not fit on a narrower target
+                "Optionisize" => quote! {
+                    if let Some(v) = table.get::<i64>($offset, None) {
+                        *$name = Some(isize::try_from(v).map_err(|_| $error::Overflow { field: $(quoted(field)) })?);
+                    }
+                },
+                "Optionusize" => quote! {
+                    if let Some(v) = table.get::<u64>($offset, None) {
+                        *$name = Some(usize::try_from(v).map_err(|_| $error::Overflow { field: $(quoted(field)) })?);
+                    }
+                },
                 _ => panic!("Not supported: {}", joined),
             }
-        } else if joined.starts_with("Vec") {
+        } else if self.get_outer_type_name() == "Vec" {
             let prim = joined.replace("Vec", "");
             let r = quote! {
-                let fb_$name = table.get::<$fuo<$fvec<$(prim.clone())>>>($offset, None);
-                if let Some(v) = fb_$name {
-                    *$name = v.iter().map(|s|s).collect();
+                let fb_$field = table.get::<$fuo<$fvec<$(prim.clone())>>>($offset, None);
+                if let Some(v) = fb_$field {
+                    *$name = v.iter().collect();
                 }
             };
             match joined.as_str() {
                 "VecString" => quote! {
-                    let fb_$name = table.get::<$fuo<$fvec<'a, $fuo<&'a str>>>>($offset, None);
-                    if let Some(v) = fb_$name {
+                    let fb_$field = table.get::<$fuo<$fvec<'buf, $fuo<&'buf str>>>>($offset, None);
+                    if let Some(v) = fb_$field {
                         *$name = v.iter().map(|s|s.to_string()).collect();
                     }
                 },
                 "Vecchar" => quote! {
-                    let fb_$name = table.get::<$fuo<$fvec<u32>>>($offset, None);
-                    if let Some(c) = fb_$name {
-                        *$name = c.iter().filter_map(|s| char::from_u32(s)).collect();
+                    let fb_$field = table.get::<$fuo<$fvec<u32>>>($offset, None);
+                    if let Some(c) = fb_$field {
+                        *$name = c.iter().filter_map(char::from_u32).collect();
                     }
                 },
                 "Vecbool" => r,
                 "Vecf32" => r,
                 "Vecf64" => r,
                 "Veci8" => quote! {
-                    let fb_$name = table.get::<$fuo<$fvec<$prim>>>($offset, None);
-                    if let Some(vb) = fb_$name {
+                    let fb_$field = table.get::<$fuo<$fvec<$prim>>>($offset, None);
+                    if let Some(vb) = fb_$field {
                         let vec_u8 = vb.bytes().to_vec();
                         let slice_u8 = vec_u8.as_slice();
                         let slice_i8 = &*(slice_u8 as *const _  as *const [i8]);
@@ -389,8 +1775,8 @@ impl FieldReceiver {
                     }
                 },
                 "Vecu8" => quote! {
-                    let fb_$name = table.get::<$fuo<$fvec<$prim>>>($offset, None);
-                    if let Some(vb) = fb_$name {
+                    let fb_$field = table.get::<$fuo<$fvec<$prim>>>($offset, None);
+                    if let Some(vb) = fb_$field {
                         *$name = vb.bytes().to_vec();
                     }
                 },
@@ -400,34 +1786,46 @@ impl FieldReceiver {
                 "Vecu32" => r,
                 "Veci64" => r,
                 "Vecu64" => r,
-                _ => panic!("Not supported: {}", joined),
+                _ => {
+                    let nested = &self.get_inner_type_name();
+                    quote! {
+                        let fb_$field = table.get::<$fuo<$fvec<'buf, <$nested as $lean_buffer>::Followed<'buf>>>>($offset, None);
+                        if let Some(v) = fb_$field {
+                            *$name = v.iter().map(<$nested as $lean_buffer>::try_inflate_slot).collect::<Result<_, _>>()?;
+                        }
+                    }
+                }
             }
         } else {
+            // a left out scalar keeps its default, which `new_object` assigned already
+            let elided = self.get_elided_default().is_some();
+            let or = |zero: &str, current: String| match elided {
+                true => current,
+                false => zero.to_string(),
+            };
             let r = quote! {
-                *$name = table.get::<$(joined.clone())>($offset, Some(0)).unwrap();
+                *$name = table.get::<$(joined.clone())>($offset, Some($(or("0", format!("*{}", name))))).unwrap();
             };
             match joined.as_str() {
                 "String" => quote! {
                     if let Some(s) = table.get::<$fuo<&str>>($offset, None) {
                         *$name = s.to_string();
-                    }else {
-                        *$name = "".to_string();
                     }
                 },
                 "char" => quote! {
-                    let $(name)_u32 = table.get::<u32>($offset, Some(0)).unwrap();
-                    if let Some(c) = std::char::from_u32($(name)_u32) {
+                    let $(field)_u32 = table.get::<u32>($offset, Some($(or("0", format!("u32::from(*{})", name))))).unwrap();
+                    if let Some(c) = std::char::from_u32($(field)_u32) {
                         *$name = c;
                     }
                 },
                 "bool" => quote! {
-                    *$name = table.get::<bool>($offset, Some(false)).unwrap();
+                    *$name = table.get::<bool>($offset, Some($(or("false", format!("*{}", name))))).unwrap();
                 },
                 "f32" => quote! {
-                    *$name = table.get::<f32>($offset, Some(0.0)).unwrap();
+                    *$name = table.get::<f32>($offset, Some($(or("0.0", format!("*{}", name))))).unwrap();
                 },
                 "f64" => quote! {
-                    *$name = table.get::<f64>($offset, Some(0.0)).unwrap();
+                    *$name = table.get::<f64>($offset, Some($(or("0.0", format!("*{}", name))))).unwrap();
                 },
                 "i8" => r,
                 "u8" => r,
@@ -437,7 +1835,84 @@ impl FieldReceiver {
                 "u32" => r,
                 "i64" => r,
                 "u64" => r,
-                _ => panic!("Not supported: {}", joined),
+                // with an explicit width, that may not fit on a narrower target
+                "isize" => quote! {
+                    *$name = isize::try_from(table.get::<i64>($offset, Some($(or("0", format!("*{} as i64", name))))).unwrap())
+                        .map_err(|_| $error::Overflow { field: $(quoted(field)) })?;
+                },
+                "usize" => quote! {
+                    *$name = usize::try_from(table.get::<u64>($offset, Some($(or("0", format!("*{} as u64", name))))).unwrap())
+                        .map_err(|_| $error::Overflow { field: $(quoted(field)) })?;
+                },
+                // inline, as 16 little endian bytes
+                "i128" => quote! {
+                    if let Some(a) = table.get::<$farray<'buf, u8, 16>>($offset, None) {
+                        *$name = i128::from_le_bytes(a.into());
+                    }
+                },
+                "u128" => quote! {
+                    if let Some(a) = table.get::<$farray<'buf, u8, 16>>($offset, None) {
+                        *$name = u128::from_le_bytes(a.into());
+                    }
+                },
+                _ => {
+                    let nested = &self.get_type_name();
+                    quote! {
+                        if let Some(n) = table.get::<<$nested as $lean_buffer>::Followed<'buf>>($offset, None) {
+                            *$name = <$nested as $lean_buffer>::try_inflate_slot(n)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// What the verifier checks in the slot, before `as_assigned_property` reads it
+    fn as_verified_type(&self) -> Tokens<Rust> {
+        let fuo = &rust::import("flatbuffers", "ForwardsUOffset");
+        let fvec = &rust::import("flatbuffers", "Vector");
+        let verified_array = &rust::import("lean_buffer::traits", "VerifiedArray");
+        let verified_entry = &rust::import("lean_buffer::traits", "VerifiedEntry");
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+
+        let ty = path_visitor::get_idents_from_path(&self.ty);
+        let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
+
+        if let Some((elem, len)) = self.get_array_type() {
+            quote!($verified_array<$elem, $len>)
+        } else if let Some(inner) = self.get_optional_inner() {
+            inner.as_verified_type()
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            inner.as_verified_type()
+        } else if let Some((key, value)) = self.get_map_entry() {
+            quote!($fuo<$fvec<'_, $fuo<$verified_entry<$(key.as_verified_type()), $(value.as_verified_type())>>>>)
+        } else if self.get_outer_type_name() == "Option" {
+            match joined.as_str() {
+                "Optionchar" => quote!(u32),
+                "Optionisize" => quote!(i64),
+                "Optionusize" => quote!(u64),
+                _ => quote!($(joined.replace("Option", ""))),
+            }
+        } else if self.get_outer_type_name() == "Vec" {
+            match joined.as_str() {
+                "VecString" => quote!($fuo<$fvec<'_, $fuo<&str>>>),
+                "Vecchar" => quote!($fuo<$fvec<'_, u32>>),
+                "Vecbool" | "Vecf32" | "Vecf64" | "Veci8" | "Vecu8" | "Veci16" | "Vecu16"
+                | "Veci32" | "Vecu32" | "Veci64" | "Vecu64" => {
+                    quote!($fuo<$fvec<'_, $(joined.replace("Vec", ""))>>)
+                }
+                _ => quote!($fuo<$fvec<'_, <$(self.get_inner_type_name()) as $lean_buffer>::Verified>>),
+            }
+        } else {
+            match joined.as_str() {
+                "String" => quote!($fuo<&str>),
+                "char" => quote!(u32),
+                "isize" => quote!(i64),
+                "usize" => quote!(u64),
+                "i128" | "u128" => quote!($verified_array<u8, 16>),
+                "bool" | "f32" | "f64" | "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64"
+                | "u64" => quote!($joined),
+                _ => quote!(<$(self.get_type_name()) as $lean_buffer>::Verified),
             }
         }
     }
@@ -447,12 +1922,26 @@ impl FieldReceiver {
         let ty = path_visitor::get_idents_from_path(&self.ty);
         let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
 
-        if joined.starts_with("Option") {
+        if let Some((elem, _)) = self.get_array_type() {
+            // aligned like its elements
+            match elem.as_str() {
+                "f64" | "u64" | "i64" => 1,
+                "f32" | "u32" | "i32" => 5,
+                "u16" | "i16" => 6,
+                _ => 7,
+            }
+        } else if let Some(inner) = self.get_optional_inner() {
+            inner.to_sorting_priority()
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            inner.to_sorting_priority()
+        } else if self.get_map_entry().is_some() {
+            // vector of offsets to the entry tables
+            2
+        } else if self.get_outer_type_name() == "Option" {
             match joined.as_str() {
                 "Optionf64" => 1,
                 "Optionu64" => 1,
                 "Optioni64" => 1,
-                "OptionString" => 4,
                 "Optionf32" => 5,
                 "Optionu32" => 5,
                 "Optioni32" => 5,
@@ -462,9 +1951,11 @@ impl FieldReceiver {
                 "Optionbool" => 7,
                 "Optionu8" => 7,
                 "Optioni8" => 7,
+                "Optionusize" => 1,
+                "Optionisize" => 1,
                 _ => panic!("Not supported: {}", joined),
             }
-        } else if joined.starts_with("Vec") {
+        } else if self.get_outer_type_name() == "Vec" {
             let r = 2;
             match joined.as_str() {
                 "VecString" => r,
@@ -480,7 +1971,8 @@ impl FieldReceiver {
                 "Vecu32" => r,
                 "Veci64" => r,
                 "Vecu64" => r,
-                _ => panic!("Not supported: {}", joined),
+                // vector of offsets to the nested tables
+                _ => r,
             }
         } else {
             match joined.as_str() {
@@ -497,57 +1989,81 @@ impl FieldReceiver {
                 "bool" => 7,
                 "u8" => 7,
                 "i8" => 7,
-                _ => panic!("Not supported: {}", joined),
+                "usize" => 1,
+                "isize" => 1,
+                // aligned like bytes
+                "u128" => 7,
+                "i128" => 7,
+                // offset to the nested table
+                _ => 4,
             }
         }
     }
 
     fn encode_flatten(&self, offset: usize) -> Tokens<Rust> {
-        let name = &self.ident.clone().unwrap().to_string();
+        let inline_array = &rust::import("lean_buffer::traits", "InlineArray");
+        let name = &self.get_binding();
 
         let ty = path_visitor::get_idents_from_path(&self.ty);
         let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
 
-        if joined.starts_with("Option") {
+        if self.get_array_type().is_some() {
+            quote! {
+                builder.push_slot_always($offset, $inline_array($name));
+            }
+        } else if let Some(inner) = self.get_optional_inner() {
+            let inner_encoded = inner.encode_flatten(offset);
+            match self.get_unnested_binding(offset) {
+                Some(binding) => quote! {
+                    if let Some($(&binding)) = $(&binding) {
+                        $inner_encoded
+                    }
+                },
+                None => quote! {
+                    if let Some($name) = $name {
+                        $inner_encoded
+                    }
+                },
+            }
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            let inner_encoded = inner.encode_flatten(offset);
+            // offsets are already unwrapped by `encode_flatten_unnested`
+            match self.get_unnested_binding(offset) {
+                Some(_) => inner_encoded,
+                None => quote! {
+                    {
+                        $(self.unwrap_binding())
+                        $inner_encoded
+                    }
+                },
+            }
+        } else if self.get_map_entry().is_some() {
+            quote! {
+                builder.push_slot_always($offset, vec_$offset);
+            }
+        } else if self.get_outer_type_name() == "Option" {
             let p = joined.replace("Option", "");
             let prim = p.as_str();
 
+            // always pushed, a value that equals the default is still present
             let r = quote! {
-                if let Some(v) = self.$name {
-                    builder.push_slot::<$prim>($offset, v, 0);
+                if let Some(v) = *$name {
+                    builder.push_slot_always::<$prim>($offset, v);
                 }
             };
             match joined.as_str() {
-                "OptionString" => quote! {
-                    if let Some(v) = self.$name.clone() {
-                        let str_$offset = builder.create_string(v.as_str());
-                        builder.push_slot_always($offset, str_$offset);
-                    }
-                },
                 "Optionchar" =>
                 // TODO test endianness
                 {
                     quote! {
-                        if let Some(v) = self.$name {
-                            builder.push_slot_always($offset, v as u32);
+                        if let Some(v) = *$name {
+                            builder.push_slot_always::<u32>($offset, v as u32);
                         }
                     }
                 }
-                "Optionbool" => quote! {
-                    if let Some(v) = self.$name {
-                        builder.push_slot::<bool>($offset, v, false);
-                    }
-                },
-                "Optionf32" => quote! {
-                    if let Some(v) = self.$name {
-                        builder.push_slot::<f32>($offset, v, 0.0);
-                    }
-                },
-                "Optionf64" => quote! {
-                    if let Some(v) = self.$name {
-                        builder.push_slot::<f64>($offset, v, 0.0);
-                    }
-                },
+                "Optionbool" => r,
+                "Optionf32" => r,
+                "Optionf64" => r,
                 "Optioni8" => r,
                 "Optionu8" => r,
                 "Optioni16" => r,
@@ -556,9 +2072,19 @@ impl FieldReceiver {
                 "Optionu32" => r,
                 "Optioni64" => r,
                 "Optionu64" => r,
+                "Optionisize" => quote! {
+                    if let Some(v) = *$name {
+                        builder.push_slot_always::<i64>($offset, v as i64);
+                    }
+                },
+                "Optionusize" => quote! {
+                    if let Some(v) = *$name {
+                        builder.push_slot_always::<u64>($offset, v as u64);
+                    }
+                },
                 _ => panic!("Not supported: {}", joined),
             }
-        } else if joined.starts_with("Vec") {
+        } else if self.get_outer_type_name() == "Vec" {
             let r = quote! {
                 builder.push_slot_always($offset, vec_$offset);
             };
@@ -576,11 +2102,13 @@ impl FieldReceiver {
                 "Vecu32" => r,
                 "Veci64" => r,
                 "Vecu64" => r,
-                _ => panic!("Not supported: {}", joined),
+                _ => r,
             }
         } else {
+            let elided = self.get_elided_default();
+            let or = |zero: &str| elided.clone().unwrap_or(zero.to_string());
             let r = quote! {
-                builder.push_slot::<$(joined.clone())>($offset, self.$name, 0);
+                builder.push_slot::<$(joined.clone())>($offset, *$name, $(or("0")));
             };
             match joined.as_str() {
                 "String" => quote! {
@@ -589,18 +2117,23 @@ impl FieldReceiver {
                 "char" =>
                 // TODO test endianness
                 {
-                    quote! {
-                      builder.push_slot_always($offset, self.$name as u32);
+                    match &elided {
+                        Some(d) => quote! {
+                          builder.push_slot::<u32>($offset, u32::from(*$name), $d);
+                        },
+                        None => quote! {
+                          builder.push_slot_always($offset, *$name as u32);
+                        },
                     }
                 }
                 "bool" => quote! {
-                  builder.push_slot::<bool>($offset, self.$name, false);
+                  builder.push_slot::<bool>($offset, *$name, $(or("false")));
                 },
                 "f32" => quote! {
-                  builder.push_slot::<f32>($offset, self.$name, 0.0);
+                  builder.push_slot::<f32>($offset, *$name, $(or("0.0")));
                 },
                 "f64" => quote! {
-                  builder.push_slot::<f64>($offset, self.$name, 0.0);
+                  builder.push_slot::<f64>($offset, *$name, $(or("0.0")));
                 },
                 "i8" => r,
                 "u8" => r,
@@ -610,33 +2143,85 @@ impl FieldReceiver {
                 "u32" => r,
                 "i64" => r,
                 "u64" => r,
-                _ => panic!("Not supported: {}", joined),
+                "isize" => quote! {
+                  builder.push_slot::<i64>($offset, *$name as i64, $(or("0")));
+                },
+                "usize" => quote! {
+                  builder.push_slot::<u64>($offset, *$name as u64, $(or("0")));
+                },
+                "i128" => quote! {
+                  builder.push_slot_always($offset, $inline_array(&$name.to_le_bytes()));
+                },
+                "u128" => quote! {
+                  builder.push_slot_always($offset, $inline_array(&$name.to_le_bytes()));
+                },
+                _ => quote! {
+                  builder.push_slot_always($offset, nested_$offset);
+                },
             }
         }
     }
 
     fn encode_flatten_unnested(&self, offset: usize) -> Tokens<Rust> {
         let wip_offset = &rust::import("flatbuffers", "WIPOffset");
-        let name = &self.ident.clone().unwrap().to_string();
+        let lean_buffer = &rust::import("lean_buffer::traits", "LeanBuffer");
+        let name = &self.get_binding();
         let ty = path_visitor::get_idents_from_path(&self.ty);
         let joined = ty.iter().map(|i| i.to_string()).collect::<String>();
 
-        if joined.starts_with("Option") {
+        if let Some(inner) = self.get_optional_inner() {
+            match self.get_unnested_binding(offset) {
+                Some(binding) => {
+                    let inner_unnested = inner.encode_flatten_unnested(offset);
+                    quote! {
+                        let $(&binding) = if let Some($name) = $name {
+                            $inner_unnested
+                            Some($(&binding))
+                        } else {
+                            None
+                        };
+                    }
+                }
+                None => quote!(),
+            }
+        } else if let Some(inner) = self.get_wrapped_inner() {
+            match self.get_unnested_binding(offset) {
+                Some(_) => {
+                    let inner_unnested = inner.encode_flatten_unnested(offset);
+                    quote! {
+                        $(self.unwrap_binding())
+                        $inner_unnested
+                    }
+                }
+                None => quote!(),
+            }
+        } else if let Some((key, value)) = self.get_map_entry() {
+            let entry_table = generate_table(&[&key, &value]);
+            quote! {
+                let mut entries_$offset = $name.iter().collect::<Vec<_>>();
+                entries_$offset.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                let entry_offsets_$offset = entries_$offset
+                    .into_iter()
+                    .map(|($(key.get_binding()), $(value.get_binding()))| { $entry_table })
+                    .collect::<Vec<_>>();
+                let vec_$offset = builder.create_vector(entry_offsets_$offset.as_slice());
+            }
+        } else if self.get_array_type().is_some() || self.get_outer_type_name() == "Option" {
             quote!()
-        } else if joined.starts_with("Vec") {
+        } else if self.get_outer_type_name() == "Vec" {
             let r = quote! {
-                let vec_$offset = builder.create_vector(&self.$name.as_slice());
+                let vec_$offset = builder.create_vector($name);
             };
             match joined.as_str() {
                 "VecString" => quote! {
-                  let strs_vec_$offset = self.$name.iter()
+                  let strs_vec_$offset = $name.iter()
                   .map(|s|builder.create_string(s.as_str()))
                   .collect::<Vec<$wip_offset<&str>>>();
                   let vec_$offset = builder.create_vector(strs_vec_$offset.as_slice());
                 },
                 "Vecchar" => quote! {
-                    let vec_conversion_$offset: Vec<u32> = self.$name.iter().map(|s|u32::from(*s)).collect();
-                    let vec_$offset = builder.create_vector(&vec_conversion_$offset.as_slice());
+                    let vec_conversion_$offset: Vec<u32> = $name.iter().map(|s|u32::from(*s)).collect();
+                    let vec_$offset = builder.create_vector(vec_conversion_$offset.as_slice());
                 },
                 "Vecbool" => r,
                 "Vecf32" => r,
@@ -649,13 +2234,18 @@ impl FieldReceiver {
                 "Vecu32" => r,
                 "Veci64" => r,
                 "Vecu64" => r,
-                _ => panic!("Not supported: {}", joined),
+                _ => quote! {
+                    let nested_vec_$offset = $name.iter()
+                    .map(|n|$lean_buffer::flatten_slot(n, builder))
+                    .collect::<Vec<_>>();
+                    let vec_$offset = builder.create_vector(nested_vec_$offset.as_slice());
+                },
             }
         } else {
             let r = quote!();
             match joined.as_str() {
                 "String" => quote! {
-                    let str_$offset = builder.create_string(self.$name.as_str());
+                    let str_$offset = builder.create_string($name);
                 },
                 "char" => r,
                 "bool" => r,
@@ -669,7 +2259,13 @@ impl FieldReceiver {
                 "u32" => r,
                 "i64" => r,
                 "u64" => r,
-                _ => panic!("Not supported: {}", joined),
+                "isize" => r,
+                "usize" => r,
+                "i128" => r,
+                "u128" => r,
+                _ => quote! {
+                    let nested_$offset = $lean_buffer::flatten_slot($name, builder);
+                },
             }
         }
     }
@@ -681,6 +2277,39 @@ mod tests {
 
     use super::*;
 
+    fn receiver_of(source: &str) -> darling::Result<InputReceiver> {
+        InputReceiver::from_derive_input(&syn::parse_str(source).unwrap())
+    }
+
+    /// The generated code of `source`, normalized as `normalize` does
+    fn code_of(source: &str) -> String {
+        let tokens = &mut rust::Tokens::new();
+        receiver_of(source)
+            .unwrap()
+            .generate_tokens(tokens, None, None, None);
+        normalize(&tokens_to_pretty_string(tokens))
+    }
+
+    /// Drops whitespace, and the trailing commas a formatter adds where it breaks a line,
+    /// so the assertions hold however the code is laid out
+    fn normalize(code: &str) -> String {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        code.replace(",)", ")")
+            .replace(",}", "}")
+            .replace(",]", "]")
+            .replace(",>", ">")
+    }
+
+    #[track_caller]
+    fn assert_contains(code: &str, expected: &str) {
+        assert!(code.contains(&normalize(expected)), "missing `{}`", expected);
+    }
+
+    #[track_caller]
+    fn assert_lacks(code: &str, unexpected: &str) {
+        assert!(!code.contains(&normalize(unexpected)), "unexpected `{}`", unexpected);
+    }
+
     #[test]
     fn it_works() {
         let input = syn::parse_str(
@@ -719,4 +2348,675 @@ mod tests {
 
         assert_eq!(15, fields.len());
     }
+
+    #[test]
+    fn nested_struct_field() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Order {
+                    id: u64,
+                    customer: Customer,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "impl traits::LeanBuffer for self::Order");
+        assert_contains(&code, "let self::Order { id: __lb_id, customer: __lb_customer } = self;");
+        assert_contains(&code, "traits::LeanBuffer::flatten_slot(__lb_customer, builder)");
+        assert_contains(&code, "<Customer as traits::LeanBuffer>::try_inflate_slot(n)?");
+        assert_contains(&code, "customer: <Customer as traits::LeanBuffer>::new_slot()");
+        // the caller owns the builder, so it is neither reset nor finished
+        assert_contains(&code, "fn flatten_table(");
+        assert_lacks(&code, "builder.reset()");
+        assert_lacks(&code, "finish_minimal");
+
+        // a field may share its name with the nested table that is read
+        let code = code_of("struct Order { n: Customer }");
+        assert_contains(&code, "*__lb_n = <Customer as traits::LeanBuffer>::try_inflate_slot(n)?;");
+    }
+
+    #[test]
+    fn nested_struct_vec_field() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Order {
+                    lines: Vec<OrderLine>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "traits::LeanBuffer::flatten_slot(n, builder)");
+        assert_contains(&code, "builder.create_vector(nested_vec_4.as_slice())");
+        assert_contains(&code, "map(<OrderLine as traits::LeanBuffer>::try_inflate_slot)");
+        assert_contains(&code, "lines: Vec::<OrderLine>::new()");
+    }
+
+    #[test]
+    fn fieldless_enum() {
+        let source = r#"
+            #[derive(LeanBufferInternal)]
+            enum Status {
+                Pending,
+                Shipped = 5,
+                Delivered,
+                #[lean_buffer(fallback)]
+                Unknown,
+            }
+        "#;
+        assert!(receiver_of(source).unwrap().data.is_enum());
+        let code = code_of(source);
+
+        assert_contains(&code, "type Slot = u8;");
+        assert_contains(&code, "Self::Delivered => 6,");
+        assert_contains(&code, "_ => Self::Unknown");
+        assert_contains(&code, "fn new_slot() -> Self { Self::Pending }");
+        // the table that wraps a root discriminant is handed back unfinished
+        assert_contains(&code, "builder.end_table(wip_offset_unfinished) }");
+
+        for invalid in [
+            "enum A { B = 1 << 2 }",
+            "enum A { B = C }",
+            "enum A { #[lean_buffer(fallback)] B, #[lean_buffer(fallback)] C }",
+            "enum A {}",
+        ] {
+            assert!(receiver_of(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn smallest_discriminant_types() {
+        let d = |values: &[i128]| -> Vec<(String, i128)> {
+            values.iter().map(|v| (String::new(), *v)).collect()
+        };
+        assert_eq!("u8", smallest_discriminant_type(&d(&[0, 255])));
+        assert_eq!("u16", smallest_discriminant_type(&d(&[0, 256])));
+        assert_eq!("i8", smallest_discriminant_type(&d(&[-1, 127])));
+        assert_eq!("i16", smallest_discriminant_type(&d(&[-1, 128])));
+        assert_eq!("u64", smallest_discriminant_type(&d(&[u32::MAX as i128 + 1])));
+        assert_eq!("i64", smallest_discriminant_type(&d(&[i32::MIN as i128 - 1])));
+    }
+
+    #[test]
+    fn union_enum() {
+        let source = r#"
+            #[derive(LeanBufferInternal)]
+            enum Message {
+                Unknown,
+                Ping(Ping),
+                Data { channel: u16, payload: Vec<u8> },
+            }
+        "#;
+        assert!(receiver_of(source).unwrap().is_union());
+        let code = code_of(source);
+
+        assert_contains(&code, "(1, Some(builder.end_table(wip_offset_unfinished)))");
+        assert_contains(&code, "Self::Ping(__lb_f0) => {");
+        assert_contains(&code, "traits::LeanBuffer::flatten_slot(__lb_f0, builder)");
+        assert_contains(&code, "Self::Data { channel: __lb_channel, payload: __lb_payload } => {");
+        assert_contains(&code, "(3, Some(union_value))");
+        assert_contains(&code, "builder.push_slot_always(4, union_type);");
+        assert_contains(&code, "_ => Self::Unknown");
+        assert_contains(
+            &code,
+            "verify_union_variant::<flatbuffers::ForwardsUOffset<traits::VerifiedTable<Self, 2>>>(\"Ping\", pos)",
+        );
+        assert_contains(&code, ".visit_field::<u16>(\"channel\", 4, false)?");
+    }
+
+    #[test]
+    fn tuple_structs() {
+        for (source, destructured, constructed) in [
+            (
+                "struct Pair(u32, String);",
+                "let self::Pair(__lb_f0, __lb_f1) = self;",
+                "self::Pair(0, String::from(\"\"))",
+            ),
+            (
+                "struct Meters(f64);",
+                "let self::Meters(__lb_f0) = self;",
+                "self::Meters(0.0)",
+            ),
+            (
+                "struct Marker;",
+                "let self::Marker = self;",
+                "-> self::Marker { self::Marker }",
+            ),
+        ] {
+            let code = code_of(source);
+
+            assert_contains(&code, destructured);
+            assert_contains(&code, constructed);
+        }
+    }
+
+    #[test]
+    fn generic_structs() {
+        let code =
+            code_of("struct Envelope<T: LeanBuffer> where T: Clone { header: Header, body: T }");
+
+        assert_contains(&code, "impl<T: LeanBuffer> traits::FactoryExt<self::Envelope<T>>");
+        assert_contains(&code, "for traits::Factory<self::Envelope<T>>");
+        assert_contains(&code, "impl<T: LeanBuffer> traits::AdapterExt for self::Envelope<T>");
+        assert_contains(&code, "impl<T: LeanBuffer> traits::LeanBuffer for self::Envelope<T>");
+        assert_contains(&code, "where T: Clone");
+        assert_contains(&code, "<T as traits::LeanBuffer>::try_inflate_slot(n)?");
+
+        let code = code_of("struct View<'a> { name: String, envelope: Envelope<'a> }");
+
+        assert_contains(&code, "impl<'a> traits::AdapterExt for self::View<'a>");
+        assert_contains(
+            &code,
+            "fn try_inflate<'buf>(&self, table: &mut flatbuffers::Table<'buf>)",
+        );
+        assert_contains(&code, "<Envelope<'a> as traits::LeanBuffer>::Followed<'buf>");
+
+        // a vector of `T` needs more than `T: LeanBuffer`
+        let code = code_of("struct Series<T: LeanBuffer> { name: String, samples: Option<Vec<T>> }");
+        assert_contains(
+            &code,
+            "for<'lb> flatbuffers::Vector<'lb, <T as lean_buffer::traits::LeanBuffer>::Verified>: flatbuffers::Verifiable",
+        );
+        assert_contains(
+            &code,
+            "<<T as lean_buffer::traits::LeanBuffer>::Slot as flatbuffers::Push>::Output: 'static",
+        );
+    }
+
+    #[test]
+    fn fixed_size_arrays() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Telemetry {
+                    position: [f32; 3],
+                    id: [u8; 16],
+                }
+            "#,
+        );
+
+        assert_contains(&code, "position: [0.0; 3]");
+        assert_contains(&code, "id: [0; 16]");
+        assert_contains(&code, "builder.push_slot_always(4, traits::InlineArray(__lb_position));");
+        assert_contains(&code, "table.get::<flatbuffers::Array<'buf, u8, 16>>(6, None)");
+
+        // a field may share its name with the array that is read
+        let code = code_of("struct Pair { a: [u8; 2] }");
+        assert_contains(&code, "if let Some(a) = table.get::<flatbuffers::Array<'buf, u8, 2>>(4, None) { *__lb_a = a.into(); }");
+    }
+
+    #[test]
+    fn optional_offsets() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Profile {
+                    nickname: Option<String>,
+                    scores: Option<Vec<u32>>,
+                    address: Option<Address>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "nickname: None");
+        assert_contains(&code, "if table.vtable().get(8) != 0");
+        assert_contains(&code, "let vec_6 = if let Some(__lb_scores) = __lb_scores {");
+        assert_contains(&code, "if let Some(nested_8) = nested_8 {");
+        assert_contains(&code, "*__lb_address = Some(inner);");
+
+        // a field may share its name with the value that is read
+        let code = code_of("struct Profile { inner: Option<String> }");
+        assert_contains(&code, "*__lb_inner = Some(inner);");
+    }
+
+    #[test]
+    fn map_fields() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Sensor {
+                    metadata: HashMap<String, f64>,
+                    labels: BTreeMap<u32, String>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "metadata: <HashMap<String, f64>>::default()");
+        assert_contains(&code, "entries_4.sort_unstable_by(|a, b| a.0.cmp(b.0));");
+        assert_contains(&code, "builder.push_slot_always(6, vec_6);");
+        assert_contains(&code, "pub fn lookup_metadata<'buf>(");
+        assert_contains(&code, "key: &str");
+        assert_contains(&code, "pub fn lookup_labels<'buf>(");
+        assert_contains(&code, "key: u32");
+
+        // generated identifiers are built from the name without `r#`
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Sensor {
+                    r#loop: BTreeMap<u32, u32>,
+                    r#ref: Vec<Label>,
+                    r#char: char,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "pub fn lookup_loop<'buf>(");
+        assert_contains(&code, "if let Some(v) = fb_ref {");
+        assert_contains(&code, "if let Some(c) = std::char::from_u32(char_u32) {");
+        assert_lacks(&code, "r#loop<");
+        assert_lacks(&code, "fb_r#");
+        assert_lacks(&code, "r#char_u32");
+    }
+
+    #[test]
+    fn wide_and_pointer_sized_integers() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Record {
+                    id: u128,
+                    len: usize,
+                    offset: Option<isize>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "traits::InlineArray(&__lb_id.to_le_bytes())");
+        assert_contains(&code, "*__lb_id = u128::from_le_bytes(a.into());");
+        assert_contains(&code, "builder.push_slot::<u64>(6, *__lb_len as u64, 0);");
+        assert_contains(&code, "usize::try_from(table.get::<u64>(6, Some(0)).unwrap())");
+        assert_contains(&code, "traits::Error::Overflow { field: \"offset\" }");
+    }
+
+    #[test]
+    fn transparent_wrappers() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Shared {
+                    title: Arc<str>,
+                    note: Cow<'static, str>,
+                    blob: Box<[u8]>,
+                    count: Rc<u32>,
+                    owner: Box<Customer>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "title: <Arc<str>>::from(String::from(\"\"))");
+        assert_contains(&code, "note: <Cow<'static, str>>::from(String::from(\"\"))");
+        assert_contains(&code, "blob: <Box<[u8]>>::from(Vec::<u8>::new())");
+        assert_contains(&code, "let __lb_title: &str = __lb_title;");
+        assert_contains(&code, "let __lb_blob: &[u8] = __lb_blob;");
+        assert_contains(&code, "*__lb_count = <Rc<u32>>::from(inner);");
+        assert_contains(&code, "let __lb_owner: &Customer = __lb_owner;");
+
+        let code = code_of("struct Shared { inner: Rc<u32> }");
+        assert_contains(&code, "*__lb_inner = <Rc<u32>>::from(inner);");
+    }
+
+    #[test]
+    fn qualified_and_aliased_paths() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                #[lean_buffer(aliases(Id = "u64", Tags = "Vec<String>"))]
+                struct Account {
+                    name: std::string::String,
+                    scores: alloc::vec::Vec<core::primitive::u32>,
+                    id: Id,
+                    tags: Option<Tags>,
+                    owner: crate::model::Customer,
+                    labels: HashMap<std::string::String, u32>,
+                    digest: [core::primitive::u8; 4],
+                }
+            "#,
+        );
+
+        assert_contains(&code, "let str_4 = builder.create_string(__lb_name);");
+        assert_contains(&code, "let vec_6 = builder.create_vector(__lb_scores);");
+        assert_contains(&code, "builder.push_slot::<u64>(8, *__lb_id, 0);");
+        assert_contains(&code, "let vec_10 = if let Some(__lb_tags) = __lb_tags {");
+        assert_contains(&code, "<crate::model::Customer as traits::LeanBuffer>::new_slot()");
+        assert_contains(&code, "lookup_labels<'buf>(table: &flatbuffers::Table<'buf>, key: &str)");
+        assert_contains(&code, "table.get::<flatbuffers::Array<'buf, u8, 4>>(16, None)");
+    }
+
+    #[test]
+    fn skipped_fields() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Session {
+                    #[lean_buffer(skip)]
+                    cache: Vec<Instant>,
+                    user: String,
+                    #[lean_buffer(skip, default_fn = "Instant::now")]
+                    opened: Instant,
+                    visits: u32,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "let self::Session { cache: _, user: __lb_user, opened: _, visits: __lb_visits } = self;");
+        assert_contains(&code, "cache: Default::default()");
+        assert_contains(&code, "opened: Instant::now()");
+        assert_contains(&code, "let str_4 = builder.create_string(__lb_user);");
+        assert_contains(&code, "builder.push_slot::<u32>(6, *__lb_visits, 0);");
+        assert_lacks(&code, "builder.push_slot_always(8");
+    }
+
+    #[test]
+    fn explicit_slot_ids() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                #[lean_buffer(deprecated = 1)]
+                struct Versioned {
+                    #[lean_buffer(id = 2)]
+                    name: String,
+                    #[lean_buffer(id = 0)]
+                    version: u32,
+                    #[lean_buffer(skip)]
+                    cache: Vec<u8>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "let str_8 = builder.create_string(__lb_name);");
+        assert_contains(&code, "builder.push_slot::<u32>(4, *__lb_version, 0);");
+        assert_lacks(&code, "(6, ");
+
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                #[lean_buffer(deprecated = 0)]
+                struct Versioned {
+                    version: u32,
+                }
+            "#,
+        );
+        assert_contains(&code, "builder.push_slot::<u32>(6, *__lb_version, 0);");
+
+        for invalid in [
+            "struct A { #[lean_buffer(id = 0)] a: u32, #[lean_buffer(id = 0)] b: u32 }",
+            "struct A { #[lean_buffer(id = 0)] a: u32, #[lean_buffer(id = 2)] b: u32 }",
+            "struct A { #[lean_buffer(id = 0)] a: u32, b: u32 }",
+            "#[lean_buffer(deprecated = 0)] struct A { #[lean_buffer(id = 0)] a: u32 }",
+        ] {
+            assert!(receiver_of(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn custom_defaults() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Settings {
+                    #[lean_buffer(default = 42)]
+                    retries: u32,
+                    #[lean_buffer(default = 0.5)]
+                    ratio: f32,
+                    #[lean_buffer(default = 'x')]
+                    separator: char,
+                    #[lean_buffer(default = 8)]
+                    threads: usize,
+                    verbose: bool,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "retries: 42,");
+        assert_contains(&code, "builder.push_slot::<u32>(4, *__lb_retries, 42);");
+        assert_contains(&code, "*__lb_retries = table.get::<u32>(4, Some(*__lb_retries)).unwrap();");
+        assert_contains(&code, "builder.push_slot::<f32>(6, *__lb_ratio, 0.5);");
+        assert_contains(
+            &code,
+            "builder.push_slot::<u32>(8, u32::from(*__lb_separator), u32::from('x'));",
+        );
+        assert_contains(&code, "builder.push_slot::<u64>(10, *__lb_threads as u64, 8);");
+        assert_contains(&code, "table.get::<u64>(10, Some(*__lb_threads as u64))");
+        assert_contains(&code, "builder.push_slot::<bool>(12, *__lb_verbose, false);");
+        assert_lacks(&code, "default_object");
+
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                #[lean_buffer(default)]
+                struct Settings {
+                    #[lean_buffer(default = 42)]
+                    retries: u32,
+                    threads: usize,
+                    name: String,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "self::Settings { retries: 42, ..Default::default() }");
+        assert_contains(&code, "let default_object = <Self as Default>::default();");
+        // an absent string keeps its default
+        assert_lacks(&code, "*__lb_name = \"\".to_string();");
+        assert_contains(&code, "builder.push_slot::<u32>(4, *__lb_retries, 42);");
+        assert_contains(
+            &code,
+            "builder.push_slot::<u64>(6, *__lb_threads as u64, default_object.threads as u64);",
+        );
+
+        assert!(receiver_of("#[lean_buffer(default)] enum A { B }").is_err());
+
+        // an absent slot would not restore these
+        for invalid in [
+            "struct A { #[lean_buffer(default = Some(3))] a: Option<u32> }",
+            "struct A { #[lean_buffer(default = String::from(\"a\"))] a: String }",
+            "struct A { #[lean_buffer(default = vec![1])] a: Vec<u8> }",
+            "struct A { #[lean_buffer(default = [1; 2])] a: [u8; 2] }",
+            "struct A { #[lean_buffer(default = 1)] a: u128 }",
+            "enum A { B { #[lean_buffer(default = Rc::new(1))] b: Rc<u32> } }",
+        ] {
+            assert!(receiver_of(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn optional_scalars() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Reading {
+                    count: Option<u32>,
+                    valid: Option<bool>,
+                    ratio: Option<f64>,
+                    letter: Option<char>,
+                    len: Option<usize>,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "builder.push_slot_always::<u32>(4, v);");
+        assert_contains(&code, "builder.push_slot_always::<bool>(6, v);");
+        assert_contains(&code, "builder.push_slot_always::<f64>(8, v);");
+        assert_contains(&code, "builder.push_slot_always::<u32>(10, v as u32);");
+        assert_contains(&code, "builder.push_slot_always::<u64>(12, v as u64);");
+        assert_lacks(&code, "builder.push_slot::<");
+        assert_contains(&code, "*__lb_count = table.get::<u32>(4, None);");
+    }
+
+    #[test]
+    fn required_fields() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Login {
+                    #[lean_buffer(required)]
+                    user: String,
+                    #[lean_buffer(required)]
+                    roles: Vec<String>,
+                    attempts: u32,
+                }
+            "#,
+        );
+
+        assert_contains(
+            &code,
+            "if table.vtable().get(4) == 0 { return Err(traits::Error::MissingField { field: \"user\" }); }",
+        );
+        assert_contains(&code, "if table.vtable().get(6) == 0 {");
+        assert_lacks(&code, "if table.vtable().get(8) == 0 {");
+
+        // the entries of a required map are not
+        let code = code_of("struct Labels { #[lean_buffer(required)] labels: BTreeMap<u32, u32> }");
+        assert_contains(&code, "MissingField { field: \"labels\" }");
+        assert_lacks(&code, "MissingField { field: \"key\" }");
+        assert_lacks(&code, "MissingField { field: \"value\" }");
+
+        for invalid in [
+            "struct A { #[lean_buffer(required)] a: u32 }",
+            "struct A { #[lean_buffer(required)] a: Option<String> }",
+            "struct A { #[lean_buffer(required, skip)] a: String }",
+            "#[lean_buffer(aliases(Id = \"u64\"))] struct A { #[lean_buffer(required)] a: Id }",
+        ] {
+            assert!(receiver_of(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn renamed_and_aliased_fields() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct User {
+                    #[lean_buffer(rename = "userName", alias = "user_name", alias = "login")]
+                    name: String,
+                    #[lean_buffer(skip)]
+                    cache: Vec<u8>,
+                    age: u8,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "impl traits::Reflect for self::User {");
+        assert_contains(
+            &code,
+            "name: \"userName\", aliases: &[\"user_name\", \"login\"], id: 0",
+        );
+        assert_contains(&code, "name: \"age\", aliases: &[], id: 1");
+        assert_lacks(&code, "\"cache\"");
+        // the slot stays tied to the field
+        assert_contains(&code, "let str_4 = builder.create_string(__lb_name);");
+
+        assert!(receiver_of("struct A { #[lean_buffer(alias = \"b\")] a: u32, b: u32 }").is_err());
+
+        // names are reported without `r#`, members are still accessed with it
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Token {
+                    #[lean_buffer(required)]
+                    r#type: String,
+                }
+            "#,
+        );
+
+        assert_contains(&code, "name: \"type\", aliases: &[], id: 0");
+        assert_contains(&code, ".visit_field::<");
+        assert_contains(&code, ">(\"type\", 4, true)?");
+        assert_contains(&code, "MissingField { field: \"type\" }");
+        assert_contains(&code, "self::Token { r#type: __lb_type }");
+        assert_lacks(&code, "\"r#type\"");
+        assert!(receiver_of("struct A { #[lean_buffer(alias = \"type\")] a: u32, r#type: u32 }").is_err());
+    }
+
+    #[test]
+    fn verified_fields() {
+        let code = code_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                struct Frame {
+                    #[lean_buffer(required)]
+                    name: String,
+                    lines: Vec<OrderLine>,
+                    tags: HashMap<String, u32>,
+                    checksum: [u8; 4],
+                    len: Option<usize>,
+                    id: u128,
+                }
+            "#,
+        );
+
+        assert_contains(
+            &code,
+            "type Verified = flatbuffers::ForwardsUOffset<traits::VerifiedTable<Self>>;",
+        );
+        assert_contains(
+            &code,
+            ".visit_field::<flatbuffers::ForwardsUOffset<&str>>(\"name\", 4, true)?",
+        );
+        assert_contains(
+            &code,
+            "flatbuffers::Vector<'_, <OrderLine as traits::LeanBuffer>::Verified>",
+        );
+        assert_contains(
+            &code,
+            "traits::VerifiedEntry<flatbuffers::ForwardsUOffset<&str>, u32>",
+        );
+        assert_contains(
+            &code,
+            ".visit_field::<traits::VerifiedArray<u8, 4>>(\"checksum\", 10, false)?",
+        );
+        assert_contains(&code, ".visit_field::<u64>(\"len\", 12, false)?");
+        assert_contains(
+            &code,
+            ".visit_field::<traits::VerifiedArray<u8, 16>>(\"id\", 14, false)?",
+        );
+    }
+
+    #[test]
+    fn file_identifiers() {
+        let code = code_of("#[lean_buffer(file_identifier = \"ENTY\")] struct Entity { a: u32 }");
+        assert_contains(&code, "const FILE_IDENTIFIER: Option<&'static str> = Some(\"ENTY\");");
+        assert_contains(&code, "<Self as traits::LeanBuffer>::FILE_IDENTIFIER");
+
+        let code = code_of("#[lean_buffer(file_identifier)] enum Status { Pending, Shipped }");
+        assert_contains(&code, &format!("Some(\"{}\");", derive_file_identifier("Status")));
+        assert_contains(&code, "fn file_identifier(&self)");
+
+        let code = code_of("struct Entity { a: u32 }");
+        assert_lacks(&code, "FILE_IDENTIFIER");
+
+        assert_eq!(4, derive_file_identifier("Entity").len());
+        assert_ne!(derive_file_identifier("Entity"), derive_file_identifier("EntityVecs"));
+
+        for invalid in ["", "ENT", "ENTITY"] {
+            let source = format!("#[lean_buffer(file_identifier = \"{}\")] struct A {{ a: u32 }}", invalid);
+            assert!(receiver_of(&source).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn inline_impls() {
+        let mut receiver = receiver_of(
+            r#"
+                #[derive(LeanBufferInternal)]
+                #[lean_buffer(inspect)]
+                struct Entity {
+                    t_u32: u32,
+                }
+            "#,
+        )
+        .unwrap();
+        assert!(receiver.inspect);
+
+        let code = receiver.generate_inline();
+        assert!(code.starts_with("const _: () = {\nuse lean_buffer::traits;"));
+        assert!(code.ends_with("};"));
+        // the derive hands it back as tokens
+        let parsed: syn::ItemConst = syn::parse_str(&code).unwrap();
+        assert_eq!("_", parsed.ident.to_string());
+        // next to the type, which may be declared in a function body
+        assert_contains(&normalize(&code), "impl traits::AdapterExt for Entity");
+        assert_lacks(&normalize(&code), "self::Entity");
+    }
 }
@@ -1,5 +1,7 @@
 /* Recursively determine the Ident objects, from a syn::Type::Path object */
 
+use std::collections::HashMap;
+
 use syn::{GenericArgument, Ident, PathArguments, Type};
 
 /// This Visitor will be used to traverse a syn::Type::Path object and recursively determine the Ident objects
@@ -8,9 +10,10 @@ struct PathVisitor {
 }
 
 impl PathVisitor {
+    /// Only the final segment counts, so `std::vec::Vec`, `alloc::vec::Vec` and `Vec` are alike
     fn visit_type(&mut self, i: &Type) {
         if let Type::Path(p) = i {
-            for seg in &p.path.segments {
+            if let Some(seg) = p.path.segments.last() {
                 self.idents.push(seg.ident.clone());
 
                 if let PathArguments::AngleBracketed(args) = &seg.arguments {
@@ -32,16 +35,82 @@ pub fn get_idents_from_path(path: &Type) -> Vec<Ident> {
     visitor.idents
 }
 
+/// The element types of every `Vec` within a type, e.g. `T` of `Option<Vec<T>>`
+pub fn get_vec_elements(ty: &Type) -> Vec<&Type> {
+    let mut elements = Vec::new();
+    if let Type::Path(p) = ty {
+        if let Some(seg) = p.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &seg.arguments {
+                for arg in args.args.iter() {
+                    if let GenericArgument::Type(a) = arg {
+                        if seg.ident == "Vec" {
+                            elements.push(a);
+                        }
+                        elements.extend(get_vec_elements(a));
+                    }
+                }
+            }
+        }
+    }
+    elements
+}
+
+/// Replaces every declared alias, e.g. `Id` of `type Id = u64;`, with its target type,
+/// also within generic arguments, arrays, slices and references
+pub fn resolve_aliases(ty: &mut Type, aliases: &HashMap<Ident, Type>) {
+    match ty {
+        Type::Path(p) => {
+            if p.qself.is_none() {
+                if let Some(alias) = p.path.get_ident().and_then(|i| aliases.get(i)) {
+                    *ty = alias.clone();
+                    return;
+                }
+            }
+            for seg in p.path.segments.iter_mut() {
+                if let PathArguments::AngleBracketed(args) = &mut seg.arguments {
+                    for arg in args.args.iter_mut() {
+                        if let GenericArgument::Type(a) = arg {
+                            resolve_aliases(a, aliases);
+                        }
+                    }
+                }
+            }
+        }
+        Type::Array(a) => resolve_aliases(&mut a.elem, aliases),
+        Type::Slice(s) => resolve_aliases(&mut s.elem, aliases),
+        Type::Reference(r) => resolve_aliases(&mut r.elem, aliases),
+        Type::Paren(p) => resolve_aliases(&mut p.elem, aliases),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 #[test]
 fn recursively_get_idents() {
-    for path in vec![
-        syn::parse_quote!(std::vec::Vec<String>),
+    for path in [
+        syn::parse_quote!(std::vec::Vec<std::string::String>),
+        syn::parse_quote!(alloc::vec::Vec<alloc::string::String>),
         syn::parse_quote!(vec::Vec<String>),
         syn::parse_quote!(Vec<String>),
     ] {
         let idents = get_idents_from_path(&path);
-        assert!(idents.iter().any(|i| i.to_string().contains("Vec")));
-        assert!(idents.iter().any(|i| i.to_string().contains("String")));
+        let joined = idents.iter().map(|i| i.to_string()).collect::<String>();
+        assert_eq!("VecString", joined);
     }
 }
+
+#[cfg(test)]
+#[test]
+fn resolve_declared_aliases() {
+    let aliases = HashMap::from([
+        (syn::parse_quote!(Id), syn::parse_quote!(u64)),
+        (syn::parse_quote!(Name), syn::parse_quote!(String)),
+    ]);
+    let mut ty: Type = syn::parse_quote!(std::collections::HashMap<Id, Vec<Name>>);
+    resolve_aliases(&mut ty, &aliases);
+    let joined = get_idents_from_path(&ty)
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<String>();
+    assert_eq!("HashMapu64VecString", joined);
+}
@@ -10,27 +10,52 @@ use syn::DeriveInput;
 
 use internal::core::InputReceiver;
 
-#[proc_macro_derive(LeanBufferWrite)]
+#[proc_macro_derive(LeanBufferWrite, attributes(lean_buffer))]
 pub fn derive_fb_code_then_write(input: TokenStream) -> TokenStream {
     let mut out = TokenStream::new();
     // yes, nasty hack, to wrap code generation
     out.extend(TokenStream::from_str("#[derive(LeanBufferInternal)]"));
     out.extend(input.clone());
     let parsed = syn::parse::<DeriveInput>(out).expect("crash");
-    let mut receiver = InputReceiver::from_derive_input(&parsed).expect("crash");
+    let mut receiver = match InputReceiver::from_derive_input(&parsed) {
+        Ok(receiver) => receiver,
+        Err(e) => return e.write_errors().into(),
+    };
     receiver.write_to_out_dir(None, None, None);
     TokenStream::new()
 }
 
 /// Not-prettified output, will be collated later, then hopefully prettified
-#[proc_macro_derive(LeanBufferRaw)]
+#[proc_macro_derive(LeanBufferRaw, attributes(lean_buffer))]
 pub fn derive_fb_code_then_write_raw(input: TokenStream) -> TokenStream {
     let mut out = TokenStream::new();
     // yes, nasty hack, to wrap code generation
     out.extend(TokenStream::from_str("#[derive(LeanBufferInternal)]"));
     out.extend(input.clone());
     let parsed = syn::parse::<DeriveInput>(out).expect("crash");
-    let mut receiver = InputReceiver::from_derive_input(&parsed).expect("crash");
+    let mut receiver = match InputReceiver::from_derive_input(&parsed) {
+        Ok(receiver) => receiver,
+        Err(e) => return e.write_errors().into(),
+    };
     receiver.write_raw_to_out_dir(None, None, None);
     TokenStream::new()
 }
+
+/// Returns the impls inline, so neither a `build.rs` nor an `include!` is needed,
+/// `#[lean_buffer(inspect)]` also writes them to `<name>_lb_inspect.rs` in `OUT_DIR`
+#[proc_macro_derive(LeanBuffer, attributes(lean_buffer))]
+pub fn derive_fb_code_inline(input: TokenStream) -> TokenStream {
+    let mut out = TokenStream::new();
+    // yes, nasty hack, to wrap code generation
+    out.extend(TokenStream::from_str("#[derive(LeanBufferInternal)]"));
+    out.extend(input.clone());
+    let parsed = syn::parse::<DeriveInput>(out).expect("crash");
+    let mut receiver = match InputReceiver::from_derive_input(&parsed) {
+        Ok(receiver) => receiver,
+        Err(e) => return e.write_errors().into(),
+    };
+    if receiver.inspect {
+        receiver.write_inspect_to_out_dir();
+    }
+    TokenStream::from_str(&receiver.generate_inline()).expect("The generated code does not parse")
+}
@@ -1,12 +1,237 @@
-use std::marker::PhantomData;
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
 
-use flatbuffers::{FlatBufferBuilder, Table};
+use flatbuffers::{
+    emplace_scalar_array, read_scalar_at, EndianScalar, FlatBufferBuilder, Follow, ForwardsUOffset,
+    InvalidFlatbuffer, Push, Table, TableFinishedWIPOffset, UOffsetT, Vector, VectorIter,
+    Verifiable, Verifier, VerifierOptions, WIPOffset, FILE_IDENTIFIER_LENGTH, SIZE_SIZEPREFIX,
+    SIZE_UOFFSET,
+};
 
 /// Applied to the struct
 pub trait AdapterExt {
-    fn flatten(&self, builder: &mut FlatBufferBuilder);
+    /// Writes the table into a builder owned by the caller, without resetting
+    /// or finishing it, e.g. to embed it in a larger message
+    fn flatten_table<'b>(
+        &self,
+        builder: &mut FlatBufferBuilder<'b>,
+    ) -> WIPOffset<TableFinishedWIPOffset>;
+    /// Written after the root offset on finish, see `LeanBuffer::FILE_IDENTIFIER`
+    fn file_identifier(&self) -> Option<&'static str> {
+        None
+    }
+    /// Resets the builder, then finishes it with the table as its root
+    fn flatten(&self, builder: &mut FlatBufferBuilder) {
+        builder.reset();
+        let root = self.flatten_table(builder);
+        builder.finish(root, self.file_identifier());
+    }
+    /// Flattens into a buffer of its own, read back by `FactoryExt::from_bytes`
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        self.flatten(&mut builder);
+        builder.finished_data().to_vec()
+    }
+    /// As `flatten`, but the buffer starts with its own length, as a 32-bit prefix
+    fn flatten_size_prefixed(&self, builder: &mut FlatBufferBuilder) {
+        builder.reset();
+        let root = self.flatten_table(builder);
+        builder.finish_size_prefixed(root, self.file_identifier());
+    }
+    /// Read back by `FactoryExt::from_size_prefixed_bytes`
+    fn to_size_prefixed_bytes(&self) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        self.flatten_size_prefixed(&mut builder);
+        builder.finished_data().to_vec()
+    }
+    /// Writes one size-prefixed frame, read back by `FactoryExt::read_frame`
+    fn write_frame(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(&self.to_size_prefixed_bytes())
+    }
 }
 
+/// Applied to a slice of derived objects, written as a root vector of their tables,
+/// so they share one buffer and its vtables
+pub trait BatchExt {
+    /// Resets the builder, then finishes it with the vector as its root
+    fn flatten_batch(&self, builder: &mut FlatBufferBuilder);
+    /// Flattens into a buffer of its own, read back by `FactoryExt::from_batch_bytes`
+    fn batch_to_bytes(&self) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        self.flatten_batch(&mut builder);
+        builder.finished_data().to_vec()
+    }
+}
+
+impl<T: AdapterExt + LeanBuffer> BatchExt for [T] {
+    fn flatten_batch(&self, builder: &mut FlatBufferBuilder) {
+        builder.reset();
+        let tables: Vec<_> = self.iter().map(|o| o.flatten_table(builder)).collect();
+        let root = builder.create_vector(&tables);
+        builder.finish(root, T::FILE_IDENTIFIER);
+    }
+}
+
+/// Raised while inflating, when the buffer holds a value
+/// that the derived type cannot represent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// e.g. a 64-bit `usize` read on a 32-bit target
+    Overflow { field: &'static str },
+    /// The slot of a `#[lean_buffer(required)]` field is absent
+    MissingField { field: &'static str },
+    /// The buffer lacks the `LeanBuffer::FILE_IDENTIFIER` of the type it is read as
+    IdentifierMismatch { expected: &'static str },
+    /// Rejected by the verifier, e.g. truncated or out of bounds
+    Invalid(InvalidFlatbuffer),
+    /// The size prefix of a frame exceeds the limit of `FactoryExt::read_frame`
+    FrameTooLarge { len: usize, max: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Overflow { field } => {
+                write!(f, "value of `{}` overflows its type on this target", field)
+            }
+            Error::MissingField { field } => write!(f, "required field `{}` is missing", field),
+            Error::IdentifierMismatch { expected } => {
+                write!(f, "buffer lacks the file identifier `{}`", expected)
+            }
+            Error::Invalid(e) => write!(f, "invalid buffer: {}", e),
+            Error::FrameTooLarge { len, max } => {
+                write!(f, "frame of {} bytes exceeds the limit of {}", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Applied to every derived type, so it can be nested
+/// in the table of another derived type
+pub trait LeanBuffer: Sized {
+    /// Pushed into the slot of the enclosing table, e.g. the offset of the child table
+    type Slot: Push + Copy;
+    /// Followed from the slot of the enclosing table
+    type Followed<'a>: Follow<'a> + 'a;
+    /// Verifies the slot before `Followed` is read,
+    /// e.g. `ForwardsUOffset<VerifiedTable<Self>>` of a struct
+    type Verified: Verifiable;
+
+    /// Writes everything that must precede the enclosing table,
+    /// then returns what goes into its slot
+    fn flatten_slot(&self, builder: &mut FlatBufferBuilder) -> Self::Slot;
+    fn try_inflate_slot<'a>(
+        followed: <Self::Followed<'a> as Follow<'a>>::Inner,
+    ) -> Result<Self, Error>;
+    /// Stands in for an absent slot
+    fn new_slot() -> Self;
+
+    /// Of a buffer with this type at its root, or a batch thereof,
+    /// e.g. `#[lean_buffer(file_identifier = "ENTY")]`
+    const FILE_IDENTIFIER: Option<&'static str> = None;
+
+    /// Verifies the table of a struct, or of a union with its `variant` tables
+    fn verify_table(v: &mut Verifier, pos: usize, variant: u8) -> Result<(), InvalidFlatbuffer>;
+}
+
+/// Compares what follows the root offset of `data` with the file identifier of `T`, if any
+fn check_identifier<T: LeanBuffer>(data: &[u8]) -> Result<(), Error> {
+    match T::FILE_IDENTIFIER {
+        Some(expected)
+            if data.get(SIZE_UOFFSET..SIZE_UOFFSET + FILE_IDENTIFIER_LENGTH)
+                != Some(expected.as_bytes()) =>
+        {
+            Err(Error::IdentifierMismatch { expected })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The table that the root offset at `root` points to, behind a size prefix if `root` is not 0
+fn verified_root_table<'a, T: LeanBuffer>(
+    data: &'a [u8],
+    opts: &VerifierOptions,
+    root: usize,
+) -> Result<Table<'a>, Error> {
+    check_identifier::<T>(data.get(root..).unwrap_or_default())?;
+    let mut verifier = Verifier::new(opts, data);
+    <ForwardsUOffset<VerifiedTable<T>>>::run_verifier(&mut verifier, root)
+        .map_err(Error::Invalid)?;
+    // the whole 32-bit offset, everything it leads to is in bounds now
+    Ok(unsafe { Table::new(data, root + read_scalar_at::<UOffsetT>(data, root) as usize) })
+}
+
+/// The table of a derived type, or of a union `VARIANT`
+pub struct VerifiedTable<T, const VARIANT: u8 = 0>(PhantomData<T>);
+
+impl<T: LeanBuffer, const VARIANT: u8> Verifiable for VerifiedTable<T, VARIANT> {
+    fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+        T::verify_table(v, pos, VARIANT)
+    }
+}
+
+/// An entry of a map, with the key in the first slot and the value in the second
+pub struct VerifiedEntry<K, V>(PhantomData<(K, V)>);
+
+impl<K: Verifiable, V: Verifiable> Verifiable for VerifiedEntry<K, V> {
+    fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+        v.visit_table(pos)?
+            .visit_field::<K>("key", 4, false)?
+            .visit_field::<V>("value", 6, false)?
+            .finish();
+        Ok(())
+    }
+}
+
+/// A fixed-size array, as pushed by `InlineArray`
+pub struct VerifiedArray<T, const N: usize>(PhantomData<T>);
+
+impl<T, const N: usize> Verifiable for VerifiedArray<T, N> {
+    fn run_verifier(v: &mut Verifier, pos: usize) -> Result<(), InvalidFlatbuffer> {
+        v.in_buffer::<[T; N]>(pos)
+    }
+}
+
+/// Pushes a fixed-size array of scalars inline into its slot,
+/// read back as a `flatbuffers::Array`
+#[derive(Clone, Copy)]
+pub struct InlineArray<'a, T, const N: usize>(pub &'a [T; N]);
+
+impl<'a, T: EndianScalar, const N: usize> Push for InlineArray<'a, T, N> {
+    type Output = [T; N];
+
+    unsafe fn push(&self, dst: &mut [u8], _written_len: usize) {
+        emplace_scalar_array(dst, 0, self.0);
+    }
+}
+
+/// Describes the slot of a field, e.g. for a schema or a text form,
+/// names may change, the id stays tied to the slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// Of the field, or its `rename`
+    pub name: &'static str,
+    /// Earlier names, e.g. `#[lean_buffer(alias = "user_name")]`
+    pub aliases: &'static [&'static str],
+    pub id: u16,
+}
+
+/// Applied to every derived struct, lists the fields that take a slot
+pub trait Reflect {
+    const FIELDS: &'static [FieldInfo];
+
+    /// By name or by alias
+    fn field(name: &str) -> Option<&'static FieldInfo> {
+        Self::FIELDS
+            .iter()
+            .find(|f| f.name == name || f.aliases.contains(&name))
+    }
+}
 
 /// A different factory can be targeted by creating
 /// a new macro based on the internal package's
@@ -23,6 +248,158 @@ pub trait FactoryExt<T>
 where
     T: ?Sized,
 {
-    fn inflate<'a>(&self, table: &mut Table<'a>) -> T;
+    fn try_inflate<'a>(&self, table: &mut Table<'a>) -> Result<T, Error>
+    where
+        T: Sized;
+    /// Panics where `try_inflate` fails
+    fn inflate<'a>(&self, table: &mut Table<'a>) -> T
+    where
+        T: Sized,
+    {
+        match self.try_inflate(table) {
+            Ok(inflated) => inflated,
+            Err(e) => panic!("{}", e),
+        }
+    }
     fn new_object(&self) -> T;
+    /// Verifies the whole buffer with the default limits, then inflates its root
+    fn try_inflate_bytes(&self, data: &[u8]) -> Result<T, Error>
+    where
+        T: LeanBuffer,
+    {
+        self.try_inflate_bytes_with_opts(data, &VerifierOptions::default())
+    }
+    /// Verifies the whole buffer, within the depth, table and size limits of `opts`,
+    /// so nothing is read out of bounds, then inflates its root
+    fn try_inflate_bytes_with_opts(&self, data: &[u8], opts: &VerifierOptions) -> Result<T, Error>
+    where
+        T: LeanBuffer,
+    {
+        self.try_inflate(&mut self.root_table(data, opts)?)
+    }
+    /// Inflates what `AdapterExt::to_bytes` wrote, as `try_inflate_bytes` does
+    #[allow(clippy::wrong_self_convention)]
+    fn from_bytes(&self, data: &[u8]) -> Result<T, Error>
+    where
+        T: LeanBuffer,
+    {
+        self.try_inflate_bytes(data)
+    }
+    /// Inflates what `AdapterExt::to_size_prefixed_bytes` wrote,
+    /// the prefix must not claim more bytes than `data` holds
+    #[allow(clippy::wrong_self_convention)]
+    fn from_size_prefixed_bytes(&self, data: &[u8]) -> Result<T, Error>
+    where
+        T: LeanBuffer,
+    {
+        let len = data.get(..SIZE_SIZEPREFIX).map_or(data.len(), |prefix| {
+            UOffsetT::from_le_bytes(prefix.try_into().unwrap()) as usize
+        });
+        let end = SIZE_SIZEPREFIX.saturating_add(len);
+        match data.get(..end) {
+            // verified in place, the builder aligned the buffer along with its prefix
+            Some(data) => self.try_inflate(&mut verified_root_table::<T>(
+                data,
+                &VerifierOptions::default(),
+                SIZE_SIZEPREFIX,
+            )?),
+            None => Err(Error::Invalid(InvalidFlatbuffer::RangeOutOfBounds {
+                range: 0..end,
+                error_trace: Default::default(),
+            })),
+        }
+    }
+    /// Reads the next frame that `AdapterExt::write_frame` wrote, `None` at the end of
+    /// the stream, a frame longer than `max_len` bytes is rejected before it is read
+    fn read_frame(&self, reader: &mut dyn Read, max_len: usize) -> io::Result<Option<T>>
+    where
+        T: LeanBuffer,
+    {
+        let mut prefix = [0u8; SIZE_SIZEPREFIX];
+        let mut filled = 0;
+        while filled < prefix.len() {
+            match reader.read(&mut prefix[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let len = UOffsetT::from_le_bytes(prefix) as usize;
+        if len > max_len {
+            let e = Error::FrameTooLarge { len, max: max_len };
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        let mut data = vec![0; SIZE_SIZEPREFIX + len];
+        data[..SIZE_SIZEPREFIX].copy_from_slice(&prefix);
+        reader.read_exact(&mut data[SIZE_SIZEPREFIX..])?;
+        self.from_size_prefixed_bytes(&data)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    /// The table that the root offset of `data` points to, verified as a `T`, file identifier included,
+    /// e.g. for the generated `lookup_<field>`
+    fn root_table<'a>(&self, data: &'a [u8], opts: &VerifierOptions) -> Result<Table<'a>, Error>
+    where
+        T: LeanBuffer,
+    {
+        verified_root_table::<T>(data, opts, 0)
+    }
+    /// Verifies what `BatchExt::batch_to_bytes` wrote, then inflates every object
+    #[allow(clippy::wrong_self_convention)]
+    fn from_batch_bytes(&self, data: &[u8]) -> Result<Vec<T>, Error>
+    where
+        T: LeanBuffer,
+        Factory<T>: FactoryExt<T>,
+    {
+        self.batch(data, &VerifierOptions::default())?.collect()
+    }
+    /// Verifies the whole batch, within the limits of `opts`,
+    /// then inflates one object per step of the returned iterator
+    fn batch<'a>(&self, data: &'a [u8], opts: &VerifierOptions) -> Result<Batch<'a, T>, Error>
+    where
+        T: LeanBuffer,
+        Factory<T>: FactoryExt<T>,
+    {
+        check_identifier::<T>(data)?;
+        let mut verifier = Verifier::new(opts, data);
+        <ForwardsUOffset<Vector<'_, ForwardsUOffset<VerifiedTable<T>>>>>::run_verifier(
+            &mut verifier,
+            0,
+        )
+        .map_err(Error::Invalid)?;
+        let tables =
+            unsafe { <ForwardsUOffset<Vector<'a, ForwardsUOffset<Table<'a>>>>>::follow(data, 0) };
+        Ok(Batch {
+            factory: Factory {
+                phantom_data: PhantomData,
+            },
+            tables: tables.iter(),
+        })
+    }
+}
+
+/// Inflates the objects of a verified batch in order, see `FactoryExt::batch`
+pub struct Batch<'a, T> {
+    factory: Factory<T>,
+    tables: VectorIter<'a, ForwardsUOffset<Table<'a>>>,
 }
+
+impl<'a, T> Iterator for Batch<'a, T>
+where
+    Factory<T>: FactoryExt<T>,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut table = self.tables.next()?;
+        Some(self.factory.try_inflate(&mut table))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tables.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Batch<'a, T> where Factory<T>: FactoryExt<T> {}